@@ -0,0 +1,230 @@
+/**
+ * IPC API between the download service in the main process and the windows
+ */
+
+/**
+ * @typedef {Object} JobSpec 	what to download, see downloadJob() in downloader.js
+ * @property {String} url
+ * @property {String} title
+ * @property {Number} [itag] 	picked according to `preference` when missing
+ * @property {Number} [audioItag] 	audio format merged with `itag`
+ * @property {String} [preference] 	a key of PREFERENCES in playlist.js
+ * @property {String} container
+ * @property {String} qualityLabel
+ * @property {Object} [convert] 	see DEFAULT_CONVERT in ffmpeg.js
+ * @property {Object} [embed] 	see DEFAULT_EMBED in metadata.js
+ * @property {Object} [subtitles] 	see subtitleChoice() in subtitles-view.js
+ * @property {Object} [chapters] 	{ mode, template }
+ * @property {Object} [clip] 	{ start, end, accurate }
+ * @property {String} template
+ * @property {String} saveDir
+ * @property {Object} [playlist] 	{ title, index, count }
+ * @property {Boolean} [ignoreArchive]
+ */
+
+/**
+ * @typedef {Object} JobIntent 	what a window asks to queue, the service makes
+ * 								the JobSpec with the save location, template
+ * 								and settings of the options
+ * @property {String} url
+ * @property {String} title
+ * @property {Number} [itag]
+ * @property {Number} [audioItag] 	audio format merged with `itag`
+ * @property {String} [audioFormat] 	conversion of an audio-only `itag`, see
+ * 									queueUrl() in service.js
+ * @property {String} [preset] 	name of a preset, when there is no itag
+ * @property {String} [preference] 	a key of PREFERENCES in playlist.js, when
+ * 									there is no itag or preset
+ * @property {Object} [clip] 	{ start, end } as typed, see parseClip() in clip.js
+ * @property {Object} [subtitles] 	{ tracks, translateTo } to save
+ * @property {Object} [playlist] 	{ title, index, count }
+ * @property {Boolean} [numbered] 	name playlist entries with the playlist template
+ * @property {Boolean} [ignoreArchive]
+ */
+
+/**
+ * @typedef {Object} Job 	a JobSpec in the queue
+ * @property {Number} id
+ * @property {String} state 	one of STATES in queue.js
+ * @property {Number} received 	bytes, or seconds while post-processing
+ * @property {Number} total
+ * @property {String} error
+ * @property {String} [filename] 	set once the job has started
+ * @property {String} [stage] 	what an active job is doing
+ * @property {Number} [attempt]
+ * @property {Number} [retryAt] 	when a failed attempt is retried, in ms
+ * @property {String} [skipped] 	"archive" or "exists" for skipped jobs
+ */
+
+/**
+ * Requests windows can make, by method name, with the IPC channel they are
+ * invoked on. Requests in SERVICE_REQUESTS resolve with the result of the
+ * DownloadService method of the same name in service.js, the others are
+ * answered by index.js.
+ *
+ * - open(url, refresh) → { info } for videos or { playlist } for playlists
+ * - getInfo(url, refresh) → Info, see summarizeInfo() in service.js, fetched
+ *   again when refresh is set even if it is cached
+ * - getPlaylist(url) → Playlist, see getPlaylist() in playlist.js
+ * - constants() → tables and defaults, see constants() in service.js
+ * - previewTemplate(template, sample) → { error, preview } for the video last
+ *   opened with open()
+ * - parseClip(start, end, duration) → { clip, label } or { error }
+ * - matchPresets([Preset]) → [{ name, label, error }] for the video last
+ *   opened with open()
+ * - checkRule(rule) → { error }
+ * - checkProxy(url) → { error }, see checkProxy() in proxy.js
+ * - getQueue() → [Job]
+ * - queueJobs([JobIntent]) → [Job]
+ * - requeue(time, url) → [Job] for the history entry recorded then
+ * - queueBatch(text, request) → [{ line, url, queued, error }], see
+ *   queueBatch() in service.js
+ * - pause(id), resume(id), remove(id), clearDone()
+ * - searchHistory(query, outcome) → { entries: [HistoryEntry], total }
+ * - clearHistory()
+ * - archivedIds() → [videoId]
+ * - cacheStats() → { entries, size, oldest, hits, misses }, clearCache()
+ * - loadSaveDir() → path, setSaveDir() → path picked, or undefined
+ * - loadOptions() → options without secrets, setOption(key, value) rejects
+ *   keys and values windows may not set, see checkOption() in options.js
+ * - importBatch() → the text of a URL list or CSV file picked in a dialog,
+ *   or undefined
+ * - cookieStatus() → { count, saved, encrypted } of the cookies sent to
+ *   YouTube, setCookies(text) and importCookies() take a cookies.txt file or
+ *   a Cookie header and resolve with the status, plus an error if no cookie
+ *   was found, clearCookies() → status
+ * - proxyStatus() → { password, encrypted } where password is whether the
+ *   proxy has one, setProxyPassword(password) → status, removing it when
+ *   empty. The password is never sent to windows.
+ * - chooseFfmpeg(), chooseArchive(), importArchive(), exportArchive() show
+ *   dialogs, changed options are sent with the options event
+ * - copyRpcToken() puts the token of the local API on the clipboard, windows
+ *   never get it. newRpcToken() replaces it, the other options are sent with
+ *   the options event.
+ * - openPath(path), showItemInFolder(path)
+ * - takeLink() → the link the app was launched with, if any, see parseLink()
+ *   in links.js. Later links are sent to the window that called it.
+ * - listSubscriptions() → [Subscription], see subscriptions.js
+ * - addSubscription(channel, { preset, template }) → [Subscription]
+ * - updateSubscription(channelId, changes) with any of { preset, template,
+ *   enabled }, or saveDir "" to use the save location of the app,
+ *   removeSubscription(channelId)
+ * - chooseSubscriptionDir(channelId) picks the save location of a
+ *   subscription in a dialog
+ * - checkSubscriptions(channelId) → number of videos queued, for every
+ *   enabled subscription when channelId is omitted
+ */
+const REQUESTS = {
+	open: "open-url",
+	getInfo: "get-info",
+	getPlaylist: "get-playlist",
+	constants: "get-constants",
+	previewTemplate: "preview-template",
+	parseClip: "parse-clip",
+	matchPresets: "match-presets",
+	checkRule: "check-rule",
+	checkProxy: "check-proxy",
+	getQueue: "get-queue",
+	queueJobs: "queue-jobs",
+	requeue: "requeue",
+	queueBatch: "queue-batch",
+	pause: "pause-job",
+	resume: "resume-job",
+	remove: "remove-job",
+	clearDone: "clear-done",
+	searchHistory: "search-history",
+	clearHistory: "clear-history",
+	archivedIds: "archived-ids",
+	cacheStats: "cache-stats",
+	clearCache: "clear-cache",
+	loadSaveDir: "load-save-dir",
+	setSaveDir: "set-save-dir",
+	loadOptions: "load-options",
+	setOption: "set-option",
+	importBatch: "import-batch",
+	cookieStatus: "cookie-status",
+	setCookies: "set-cookies",
+	importCookies: "import-cookies",
+	clearCookies: "clear-cookies",
+	proxyStatus: "proxy-status",
+	setProxyPassword: "set-proxy-password",
+	chooseFfmpeg: "choose-ffmpeg",
+	chooseArchive: "choose-archive",
+	importArchive: "import-archive",
+	exportArchive: "export-archive",
+	copyRpcToken: "copy-rpc-token",
+	newRpcToken: "new-rpc-token",
+	openPath: "open-path",
+	showItemInFolder: "show-item-in-folder",
+	takeLink: "take-link",
+	listSubscriptions: "list-subscriptions",
+	addSubscription: "add-subscription",
+	updateSubscription: "update-subscription",
+	chooseSubscriptionDir: "choose-subscription-dir",
+	removeSubscription: "remove-subscription",
+	checkSubscriptions: "check-subscriptions",
+};
+
+/**
+ * Requests answered by the download service
+ */
+const SERVICE_REQUESTS = [
+	"open",
+	"getInfo",
+	"getPlaylist",
+	"constants",
+	"previewTemplate",
+	"parseClip",
+	"matchPresets",
+	"checkRule",
+	"checkProxy",
+	"getQueue",
+	"queueJobs",
+	"requeue",
+	"queueBatch",
+	"pause",
+	"resume",
+	"remove",
+	"clearDone",
+	"searchHistory",
+	"clearHistory",
+	"archivedIds",
+	"cacheStats",
+	"clearCache",
+];
+
+/**
+ * Events the service sends to every window, by event name, with their IPC
+ * channel
+ *
+ * - queue [Job] whenever a job is added, removed or changes state
+ * - progress Job while a job transfers or waits to retry
+ * - record HistoryEntry when a job has finished
+ * - history when the history is cleared
+ * - options Options when the main process changed them
+ * - link { url, preset, getInfo } when a link is opened with the app
+ * - subscriptions [Subscription] when a subscription changes or is checked
+ */
+const EVENTS = {
+	queue: "queue",
+	progress: "job-progress",
+	record: "history-record",
+	history: "history",
+	options: "options",
+	link: "open-link",
+	subscriptions: "subscriptions",
+};
+
+/**
+ * Thrown by the service when a request asks for something that cannot exist,
+ * like an unknown format or an invalid template, rather than failing to get it
+ */
+class RequestError extends Error {}
+
+/**
+ * Thrown by the service when a video has no format matching a request, which
+ * asking again will not change
+ */
+class FormatError extends Error {}
+
+module.exports = { REQUESTS, SERVICE_REQUESTS, EVENTS, RequestError, FormatError };
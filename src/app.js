@@ -1,13 +1,50 @@
 import { html, render } from "../node_modules/lit-html/lit-html.js";
-
-const ytdl = require("ytdl-core");
-const { ipcRenderer, shell } = require("electron");
-const path = require("path");
-const fs = require("fs");
+import { renderSettings } from "./settings.js";
+import { renderPlaylist, resetSelection } from "./playlist-view.js";
+import { renderHistory, displayDuration } from "./history-view.js";
+import { renderSubscriptions } from "./subscriptions-view.js";
+import { renderBatch } from "./batch-view.js";
+import { subtitlesPanel, subtitleChoice, resetSubtitles } from "./subtitles-view.js";
+import { constants, loadConstants } from "./constants.js";
 
 const $ = document.querySelector.bind(document);
 let saveDir = "";
-let require_video, require_audio, infoTable, info;
+let options = {};
+let require_video, require_audio, ignore_archive, infoTable, queueTable, info, playlist;
+let pickedVideo, pickedAudio; // formats picked for merging
+const convertChoice = new Map(); // itag => audio codec picked for that format
+const clipChoice = new Map(); // itag or "merged" => { start, end, clip, label, error } for that row
+let presetMatches = []; // what each preset picks for the loaded video
+let chosenPreset; // name of the preset picked in the info view
+let jobs = []; // the queue as last sent by the download service
+let archived = new Set(); // ids of the videos in the download archive
+let subscriptions = []; // followed channels as last sent by the main process
+
+const service = window.ytdl;
+
+/**
+ * Change an option and send it to the main process to be saved
+ * @param {String} key
+ * @param {*} value
+ */
+function setOption(key, value) {
+	options[key] = value;
+	service.setOption(key, value).catch(async (err) => {
+		console.error(err);
+		// show what the main process kept instead
+		showOptions(await service.loadOptions());
+	});
+	if (key === "presets" || key === "defaultPreset") matchPresets().then(renderInfoTable);
+	if (key === "presets" || key === "subscriptions") showSubscriptions();
+	if (key === "presets" || key === "defaultPreset") showBatch();
+}
+
+/**
+ * Ask the download service what each preset picks for the loaded video
+ */
+async function matchPresets() {
+	presetMatches = info ? await service.matchPresets(options.presets || []) : [];
+}
 
 /**
  * Display human readable sizes
@@ -27,40 +64,449 @@ function displaySize(size) {
 }
 
 /**
- * Download a selected format
+ * Reload the ids of the videos in the download archive, empty when it is
+ * disabled
+ */
+async function refreshArchived() {
+	archived = new Set(await service.archivedIds());
+}
+
+/**
+ * Message of an error thrown by the download service
+ * @param {Error} err
+ */
+function serviceError(err) {
+	return `${err.message || err}`.replace(/^Error invoking remote method '[^']+': /, "");
+}
+
+/**
+ * Find the most recent single format job for a format of a video
+ */
+function findJob(url, itag) {
+	for (let i = jobs.length - 1; i >= 0; i--) {
+		const j = jobs[i];
+		if (j.url === url && j.itag === itag && !j.audioItag) return j;
+	}
+	return undefined;
+}
+
+/**
+ * Range typed for a row of the info table, as checked by the download service
+ * @param {*} key 	an itag, or "merged" for the merge buttons
+ * @returns {Object} { clip, error } where clip is the { start, end } typed,
+ * 					undefined for the whole video
+ */
+function clipOf(key) {
+	const { start, end, clip, error } = clipChoice.get(key) || {};
+	return clip ? { clip: { start, end } } : { error };
+}
+
+/**
+ * Show why the last videos could not be queued, or clear it
+ * @param {Error} err 	thrown by the download service, undefined to clear
+ */
+function showQueueError(err) {
+	render(err ? html`<pre>${serviceError(err)}</pre>` : "", $("#queue-error"));
+}
+
+/**
+ * Ask the download service to queue videos. It makes the jobs with the
+ * save location, template and settings of the options.
+ * @param {...Object} intents 	see JobIntent in api.js
+ */
+function enqueue(...intents) {
+	service
+		.queueJobs(intents)
+		.then(() => showQueueError())
+		.catch((err) => {
+			console.error(err);
+			showQueueError(err);
+		});
+}
+
+/**
+ * Queue a selected format for download
  * @param {Object} target 	an entry in data.formats
  */
 const download = (target) => {
-	try {
-		const extName = target.hasVideo
-			? target.container
-			: target.container === "mp4"
-			? "m4a"
-			: target.container === "webm"
-			? "webm"
-			: "unknown";
-		const filename = `${path.join(
-			saveDir,
-			info.videoDetails.title.replace(/[\"\*\:\<\>\?\/\\\|]/g, "_") // filename save encoding
-		)}-itag${target.itag}.${extName}`;
-		const writer = fs.createWriteStream(filename);
-		const resultDiv = $(`#download-${target.itag}`);
-		render("0%", resultDiv);
-		const res = ytdl(info.videoDetails.video_url, { quality: target.itag });
-		res.pipe(writer);
-		res.on("progress", (chunkSize, received, total) => {
-			render(`${((100 * received) / total).toFixed(1)}%`, resultDiv);
-		});
-	} catch (err) {
-		render(`${err}`, resultDiv);
+	enqueue({
+		url: info.videoDetails.video_url,
+		title: info.videoDetails.title,
+		itag: target.itag,
+		audioFormat: convertChoice.get(target.itag),
+		clip: clipOf(target.itag).clip,
+		subtitles: subtitleChoice(),
+		ignoreArchive: ignore_archive.checked,
+	});
+};
+
+/**
+ * Queue a video-only and an audio-only format to be downloaded and merged
+ * @param {Object} video 	a video-only entry in data.formats
+ * @param {Object} audio 	an audio-only entry in data.formats
+ */
+const downloadMerged = (video, audio) => {
+	enqueue({
+		url: info.videoDetails.video_url,
+		title: info.videoDetails.title,
+		itag: video.itag,
+		audioItag: audio.itag,
+		clip: clipOf("merged").clip,
+		subtitles: subtitleChoice(),
+		ignoreArchive: ignore_archive.checked,
+	});
+};
+
+/**
+ * Queue the loaded video with a preset, whose rule picks the formats when
+ * the job starts
+ * @param {Object} preset 	an entry of options.presets
+ */
+const downloadPreset = (preset) => {
+	enqueue({
+		url: info.videoDetails.video_url,
+		title: info.videoDetails.title,
+		preset: preset.name,
+		subtitles: subtitleChoice(),
+		ignoreArchive: ignore_archive.checked,
+	});
+};
+
+/**
+ * Whether a choice of formats is a key of constants.preferences rather than
+ * a preset name
+ */
+const isPreference = (choice) =>
+	Object.prototype.hasOwnProperty.call(constants.preferences, choice);
+
+/**
+ * Fields of a JobIntent picking formats by a preference or by a preset
+ * @param {String} choice 	a key of constants.preferences or a preset name
+ */
+const choiceOf = (choice) => (isPreference(choice) ? { preference: choice } : { preset: choice });
+
+/**
+ * Queue entries of a playlist. Their formats are picked according to the
+ * preference or preset when they start downloading.
+ * @param {Array} items 	entries of a playlist from getPlaylist()
+ * @param {String} choice 	a key of constants.preferences or a preset name
+ * @param {Boolean} numbered 	use the playlist filename template
+ */
+const downloadPlaylist = (items, choice, numbered) => {
+	enqueue(
+		...items.map((item) => ({
+			url: item.url,
+			title: item.title,
+			...choiceOf(choice),
+			playlist: { title: playlist.title, index: item.index, count: playlist.items.length },
+			numbered,
+			ignoreArchive: ignore_archive.checked,
+		}))
+	);
+};
+
+/**
+ * Text shown for a job in the status cells
+ * @param {Object} job 	a queue job
+ */
+function jobStatus(job) {
+	const percent = job.total
+		? `${((100 * job.received) / job.total).toFixed(1)}%`
+		: "0%";
+	const maxAttempts = options.retry ? options.retry.maxAttempts : "";
+	switch (job.state) {
+		case "active":
+			if (job.retryAt > Date.now()) {
+				const wait = Math.ceil((job.retryAt - Date.now()) / 1000);
+				return `${job.lastError}, retrying in ${wait}s (attempt ${
+					job.attempt + 1
+				}/${maxAttempts})`;
+			}
+			const stage =
+				["merging", "converting", "splitting", "clipping"].includes(job.stage)
+					? `${job.stage} ${percent}`
+					: job.stage === "subtitles"
+					? "fetching subtitles"
+					: percent;
+			return job.attempt > 1
+				? `${stage} (attempt ${job.attempt}/${maxAttempts})`
+				: stage;
+		case "paused":
+			return `paused ${percent}`;
+		case "failed":
+			return `failed: ${job.error}`;
+		case "done":
+			return job.skipped === "archive"
+				? "skipped, in archive"
+				: job.skipped
+				? "skipped, file exists"
+				: "done";
+		default:
+			return job.state;
+	}
+}
+
+const renderQueue = () => {
+	if (!jobs.length) {
+		render(html``, queueTable);
+		return;
+	}
+	render(
+		html`<h3>Queue</h3>
+			<table>
+				<tbody>
+					<tr>
+						<th>Title</th>
+						<th>Format</th>
+						<th>Size</th>
+						<th>Status</th>
+						<th></th>
+					</tr>
+					${jobs.map(
+						(j) => html`<tr>
+							<td class="title">${j.title}</td>
+							<td>
+								${j.qualityLabel !== undefined
+									? `${j.container} ${j.qualityLabel}`
+									: j.preset
+									? j.preset.name
+									: constants.preferences[j.preference]}
+							</td>
+							<td>${displaySize(j.total || NaN)}</td>
+							<td>${jobStatus(j)}</td>
+							<td>
+								${j.state === "pending" || j.state === "active"
+									? html`<button @click=${() => service.pause(j.id)}>Pause</button>`
+									: j.state === "paused" || j.state === "failed"
+									? html`<button @click=${() => service.resume(j.id)}>Resume</button>`
+									: html`<button @click=${() => service.showItemInFolder(j.filename)}>
+											Show
+									  </button>`}
+								<button @click=${() => service.remove(j.id)}>Remove</button>
+							</td>
+						</tr>`
+					)}
+				</tbody>
+			</table>
+			<button @click=${() => service.clearDone()}>Clear finished</button>`,
+		queueTable
+	);
+};
+
+/**
+ * Download cell of a format in the info table
+ * @param {Object} format 	an entry in info.formats
+ */
+function downloadCell(format) {
+	const job = findJob(info.videoDetails.video_url, format.itag);
+	if (!job || job.state === "done" || job.state === "failed") {
+		return html`${job ? jobStatus(job) + " " : ""}${convertSelect(format)}<button
+				?disabled=${!!clipOf(format.itag).error}
+				@click=${(e) => download(format)}
+			>
+				Download
+			</button>`;
 	}
+	return jobStatus(job);
+}
+
+/**
+ * Start and end fields to download only part of a video
+ * @param {*} key 	an itag, or "merged" for the merge buttons
+ */
+function clipFields(key) {
+	const typed = clipChoice.get(key) || {};
+	const { error } = typed;
+	const set = async (field, value) => {
+		const range = { start: typed.start, end: typed.end, [field]: value };
+		clipChoice.set(key, range);
+		const duration = parseInt(info.videoDetails.lengthSeconds, 10) || 0;
+		const checked = await service.parseClip(range.start, range.end, duration);
+		// ignore checks overtaken by further typing
+		if (clipChoice.get(key) !== range) return;
+		clipChoice.set(key, { ...range, ...checked });
+		renderInfoTable();
+	};
+	return html`<span
+		class=${error ? "clip invalid" : "clip"}
+		title=${error || "Start and end, e.g. 12:30"}
+	>
+		<input
+			type="text"
+			placeholder="start"
+			.value=${typed.start || ""}
+			@input=${(e) => set("start", e.target.value)}
+		/>
+		<input
+			type="text"
+			placeholder="end"
+			.value=${typed.end || ""}
+			@input=${(e) => set("end", e.target.value)}
+		/>
+	</span>`;
+}
+
+/**
+ * Audio conversion picker for audio-only formats
+ * @param {Object} format 	an entry in info.formats
+ */
+function convertSelect(format) {
+	if (format.hasVideo) return "";
+	const codec = convertChoice.has(format.itag)
+		? convertChoice.get(format.itag)
+		: (options.convert || constants.defaults.convert).codec;
+	return html`<select
+		title="Convert to"
+		@change=${(e) => convertChoice.set(format.itag, e.target.value)}
+	>
+		<option value="original" .selected=${codec === "original"}>Original</option>
+		${Object.entries(constants.audioCodecs).map(
+			([key, c]) => html`<option value=${key} .selected=${codec === key}>
+				${c.label}
+			</option>`
+		)}
+	</select>`;
+}
+
+/**
+ * Preset picker and what it picks for the loaded video
+ */
+const presetBar = () => {
+	const presets = options.presets || [];
+	if (!presets.length) return "";
+	const settings = { ...constants.defaults.defaultPreset, ...options.defaultPreset };
+	const name = chosenPreset || settings.name;
+	const preset = presets.find((p) => p.name === name) || presets[0];
+	const match = presetMatches.find((m) => m.name === preset.name);
+	return html`<div class="merge-row">
+		<label>
+			Preset
+			<select
+				@change=${(e) => {
+					chosenPreset = e.target.value;
+					renderInfoTable();
+				}}
+			>
+				${presets.map(
+					(p) => html`<option value=${p.name} .selected=${p === preset}>${p.name}</option>`
+				)}
+			</select>
+		</label>
+		<button ?disabled=${!match || !match.label} @click=${() => downloadPreset(preset)}>
+			Download ${match && match.label ? `(${match.label})` : ""}
+		</button>
+		${match && !match.label ? match.error || "No format matches" : ""}
+	</div>`;
 };
 
+/**
+ * Controls to merge the best or the picked video and audio formats
+ */
+const mergeBar = () => {
+	const container = options.mergeContainer || "mkv";
+	const best = info.bestPairs[container];
+	return html`<div class="merge-row">
+		<label>
+			Merge into
+			<select
+				@change=${(e) => {
+					setOption("mergeContainer", e.target.value);
+					renderInfoTable();
+				}}
+			>
+				${constants.containers.map(
+					(c) => html`<option .selected=${c === container}>${c}</option>`
+				)}
+			</select>
+		</label>
+		${clipFields("merged")}
+		<button
+			?disabled=${!best.video || !best.audio || !!clipOf("merged").error}
+			@click=${() => downloadMerged(best.video, best.audio)}
+		>
+			Best video + best audio
+			${best.video ? `(${best.video.qualityLabel} ${best.video.container})` : ""}
+		</button>
+		<button
+			?disabled=${!pickedVideo || !pickedAudio || !!clipOf("merged").error}
+			@click=${() => downloadMerged(pickedVideo, pickedAudio)}
+		>
+			Picked video + picked audio
+		</button>
+		<label title="Re-encode clips so they start on the exact frame, slower">
+			<input
+				type="checkbox"
+				.checked=${{ ...constants.defaults.clip, ...options.clip }.accurate}
+				@change=${(e) =>
+					setOption("clip", {
+						...constants.defaults.clip,
+						...options.clip,
+						accurate: e.target.checked,
+					})}
+			/>
+			Frame-accurate clips
+		</label>
+	</div>`;
+};
+
+/**
+ * Chapters found for the loaded video
+ */
+const chaptersPanel = () => {
+	const { chapters } = info;
+	if (!chapters.length) return "";
+	const mode = { ...constants.defaults.chapters, ...options.chapters }.mode;
+	return html`<details class="chapters">
+		<summary>Chapters (${chapters.length}${mode === "none" ? ", ignored in Settings" : ""})</summary>
+		<table>
+			${chapters.map(
+				(c) => html`<tr>
+					<td>${displayDuration(Math.floor(c.start)) || "0:00"}</td>
+					<td class="title">${c.title}</td>
+				</tr>`
+			)}
+		</table>
+	</details>`;
+};
+
+/**
+ * Radio button picking a video-only or audio-only format for merging
+ * @param {Object} format 	an entry in info.formats
+ */
+function pickCell(format) {
+	if (format.hasVideo && format.hasAudio) return "";
+	const isVideo = format.hasVideo;
+	return html`<input
+		type="radio"
+		name=${isVideo ? "pick-video" : "pick-audio"}
+		title=${isVideo ? "Pick as video" : "Pick as audio"}
+		.checked=${(isVideo ? pickedVideo : pickedAudio) === format}
+		@change=${() => {
+			if (isVideo) pickedVideo = format;
+			else pickedAudio = format;
+			renderInfoTable();
+		}}
+	/>`;
+}
+
 const renderInfoTable = () => {
+	if (playlist) {
+		renderPlaylist(infoTable, playlist, downloadPlaylist, archived);
+		return;
+	}
 	if (!info) return;
+	const list = info.playlistUrl;
 
 	render(
 		html`<h3>${info.videoDetails.title}</h3>
+			${list
+				? html`<div class="merge-row">
+						This video is part of a playlist
+						<button @click=${() => openPlaylist(list)}>Open playlist</button>
+				  </div>`
+				: ""}
+			${presetBar()} ${mergeBar()}
+			${subtitlesPanel(info, options, setOption, renderInfoTable)} ${chaptersPanel()}
 			<table>
 				<tbody>
 					<tr>
@@ -73,6 +519,8 @@ const renderInfoTable = () => {
 						<th>Bitrate</th>
 						<th>AudioBitrate</th>
 						<th>Size</th>
+						<th>Merge</th>
+						<th>Clip</th>
 						<th>Download</th>
 					</tr>
 					${info.formats
@@ -92,49 +540,245 @@ const renderInfoTable = () => {
 								<td>${i.bitrate ? i.bitrate : ""}</td>
 								<td>${i.audioBitrate ? i.audioBitrate : ""}</td>
 								<td>${displaySize(i.contentLength)}</td>
-								<td id="download-${i.itag}">
-									<button @click=${(e) => download(i)}>
-										Download
-									</button>
-								</td>
+								<td>${pickCell(i)}</td>
+								<td>${clipFields(i.itag)}</td>
+								<td class="download" data-itag=${i.itag}></td>
 							</tr> `
 						)}
 				</tbody>
 			</table>`,
 		infoTable
 	);
+	for (const format of info.formats) renderDownloadCell(format);
+};
+
+/**
+ * Render the download cell of a format on its own, so progress updates leave
+ * the rest of the info table alone
+ * @param {Object} format 	an entry in info.formats
+ */
+const renderDownloadCell = (format) => {
+	const cell = infoTable.querySelector(`td.download[data-itag="${format.itag}"]`);
+	if (cell) render(downloadCell(format), cell);
 };
 
-async function getInfo() {
+/**
+ * Show a playlist loaded by the download service in the info view
+ * @param {Object} list 	from getPlaylist()
+ */
+async function showPlaylist(list) {
+	playlist = list;
+	info = undefined;
+	await refreshArchived();
+	resetSelection(playlist, archived);
+	renderInfoTable();
+	return true;
+}
+
+async function openPlaylist(url) {
+	render(html`<h3>Loading playlist...</h3>`, infoTable);
+	showQueueError();
+	try {
+		await showPlaylist(await service.getPlaylist(url));
+	} catch (err) {
+		console.error(err);
+		render(html`<pre>${serviceError(err)}</pre>`, infoTable);
+	}
+}
+
+/**
+ * Open the URL typed in #u
+ * @param {Boolean} autoPreset 	queue videos with the default preset when
+ * 								set to in Settings
+ * @param {Boolean} refresh 	fetch the info even if it is cached
+ * @returns {Promise<Boolean>} whether a video or a playlist was loaded
+ */
+async function getInfo(autoPreset = true, refresh = false) {
 	const url = $("#u").value;
 	render(html`<h3>Analyzing...</h3>`, infoTable);
+	showQueueError();
 	try {
-		info = await ytdl.getInfo(url);
+		const opened = await service.open(url, refresh);
+		if (opened.playlist) return showPlaylist(opened.playlist);
+		info = opened.info;
+		playlist = undefined;
+		pickedVideo = pickedAudio = undefined;
+		convertChoice.clear();
+		clipChoice.clear();
+		resetSubtitles();
+		chosenPreset = undefined;
+		await matchPresets();
 		renderInfoTable();
+		renderSettings($("#settings"), options, setOption, info);
+
+		const preset = autoPreset && defaultPreset();
+		if (preset) downloadPreset(preset);
+		return true;
 	} catch (err) {
 		console.error(err);
-		render(html`<pre>${err}</pre>`, infoTable);
+		render(html`<pre>${serviceError(err)}</pre>`, infoTable);
+		return false;
+	}
+}
+
+/**
+ * The default preset if Settings queue it as soon as the info is loaded and
+ * it matches the loaded video
+ * @returns {Object} an entry of options.presets, or undefined
+ */
+function defaultPreset() {
+	const settings = { ...constants.defaults.defaultPreset, ...options.defaultPreset };
+	const preset = (options.presets || []).find((p) => p.name === settings.name);
+	const match = preset && presetMatches.find((m) => m.name === preset.name);
+	return settings.auto && match && match.label ? preset : undefined;
+}
+
+/**
+ * Open a link the app was launched with, see links.js. What it queues is
+ * confirmed first unless Settings queue links without asking.
+ * @param {Object} link 	{ url, preset, getInfo }
+ */
+async function openLink(link) {
+	if (!link) return;
+	const settings = { ...constants.defaults.links, ...options.links };
+	const choice = link.preset !== undefined ? link.preset : settings.preset;
+	const fetch = link.getInfo !== undefined ? link.getInfo : settings.getInfo;
+	$("#u").value = link.url;
+	if (!choice && !fetch) return;
+	if (!(await getInfo(false))) return;
+
+	// a preset in the link replaces the default one
+	const named = (name) =>
+		(options.presets || []).find((p) => p.name.toLowerCase() === name.toLowerCase());
+	const preset = choice
+		? !isPreference(choice) && named(choice)
+		: !playlist && defaultPreset();
+	const label = isPreference(choice) ? constants.preferences[choice] : preset && preset.name;
+	if (!label) return;
+	const what = playlist
+		? `${playlist.items.length} videos of ${playlist.title}`
+		: info.videoDetails.title;
+	if (!settings.autoQueue && !confirm(`Download ${what} as ${label}?`)) return;
+	if (playlist) {
+		downloadPlaylist(playlist.items, preset ? preset.name : choice, true);
+	} else if (preset) {
+		downloadPreset(preset);
+	} else {
+		enqueue({
+			url: info.videoDetails.video_url,
+			title: info.videoDetails.title,
+			preference: choice,
+			ignoreArchive: ignore_archive.checked,
+		});
 	}
 }
 
-ipcRenderer.on("save-dir", (e, arg) => {
-	$("#save-dir").innerText = arg;
-	saveDir = arg;
+const showSaveDir = (dir) => {
+	if (!dir) return;
+	$("#save-dir").innerText = dir;
+	saveDir = dir;
+};
+
+const showOptions = (arg) => {
+	options = arg;
+	$("#max-concurrent").value = options.maxConcurrent;
+	renderSettings($("#settings"), options, setOption, info);
+	showSubscriptions();
+	showBatch();
+};
+
+const showBatch = () => {
+	renderBatch($("#batch"), {
+		options,
+		setOption,
+		request: () => ({ ignoreArchive: ignore_archive.checked }),
+		serviceError,
+	});
+};
+
+const showSubscriptions = (list = subscriptions) => {
+	subscriptions = list;
+	renderSubscriptions($("#subscriptions"), subscriptions, { options, setOption, serviceError });
+};
+
+service.on("options", showOptions);
+service.on("link", openLink);
+service.on("subscriptions", showSubscriptions);
+
+const updateQueue = (list) => {
+	jobs = list;
+	if (!queueTable) return;
+	renderQueue();
+	renderInfoTable();
+};
+
+service.on("queue", updateQueue);
+
+service.on("progress", (job) => {
+	const i = jobs.findIndex((j) => j.id === job.id);
+	if (i < 0) return;
+	jobs[i] = job;
+	if (!queueTable) return;
+	renderQueue();
+	if (playlist || !info || job.audioItag || job.url !== info.videoDetails.video_url) return;
+	const format = info.formats.find((f) => f.itag === job.itag);
+	if (format) renderDownloadCell(format);
 });
 
-window.onload = () => {
-	$("#query").addEventListener("click", getInfo);
-	ipcRenderer.send("load-save-dir");
+const renderHistoryView = () => {
+	renderHistory($("#history"), {
+		search: (query, outcome) => service.searchHistory(query, outcome),
+		requeue: (entry) =>
+			service.requeue(entry.time, entry.url).catch((err) => console.error(err)),
+		clear: () => service.clearHistory(),
+		displaySize,
+	}).catch((err) => console.error(err));
+};
+
+service.on("record", async () => {
+	renderHistoryView();
+	if (playlist) {
+		await refreshArchived();
+		renderInfoTable();
+	}
+});
+
+service.on("history", renderHistoryView);
+
+// keep the retry countdowns ticking
+setInterval(() => {
+	if (queueTable && jobs.some((j) => j.state === "active" && j.retryAt > Date.now())) {
+		renderQueue();
+		if (info && !playlist) for (const format of info.formats) renderDownloadCell(format);
+	}
+}, 1000);
+
+window.onload = async () => {
+	await loadConstants();
+	$("#query").addEventListener("click", () => getInfo());
+	$("#refresh").addEventListener("click", () => getInfo(false, true));
+	showSaveDir(await service.loadSaveDir());
+	showOptions(await service.loadOptions());
+	service.getQueue().then(updateQueue);
+	renderHistoryView();
+	service.listSubscriptions().then(showSubscriptions);
 	$("#save-dir").addEventListener("click", () => {
-		shell.openPath(saveDir);
+		service.openPath(saveDir);
 	});
 	$("#change-save-dir").addEventListener("click", () => {
-		ipcRenderer.send("set-save-dir");
+		service.setSaveDir().then(showSaveDir);
 	});
 	require_audio = $("#require-audio");
 	require_video = $("#require-video");
+	ignore_archive = $("#ignore-archive");
 	infoTable = $("#info");
+	queueTable = $("#queue");
 
 	require_audio.addEventListener("change", (e) => renderInfoTable());
 	require_video.addEventListener("change", (e) => renderInfoTable());
+	$("#max-concurrent").addEventListener("change", (e) => {
+		setOption("maxConcurrent", Math.max(1, parseInt(e.target.value, 10) || 1));
+	});
+	// links opened from now on are sent with the link event
+	openLink(await service.takeLink());
 };
@@ -0,0 +1,89 @@
+const fs = require("fs");
+const path = require("path");
+
+/**
+ * Default archive settings, stored as options.archive. The main process sets
+ * the file to archive.txt next to config.json when it is empty.
+ */
+const DEFAULT_ARCHIVE = { enabled: false, file: "" };
+
+/**
+ * Download archive compatible with yt-dlp's --download-archive: one
+ * `<extractor> <id>` line per downloaded video, `youtube <id>` for this app
+ */
+class Archive {
+	constructor(file) {
+		this.file = file;
+	}
+
+	/**
+	 * Every line of the archive, without duplicates
+	 */
+	lines() {
+		let text = "";
+		try {
+			text = fs.readFileSync(this.file, "utf8");
+		} catch (err) {
+			return [];
+		}
+		const lines = text
+			.split(/\r?\n/)
+			.map((l) => l.trim())
+			.filter((l) => l);
+		return [...new Set(lines)];
+	}
+
+	/**
+	 * Ids of the YouTube videos in the archive. The file is read every time
+	 * since yt-dlp may share it.
+	 */
+	ids() {
+		const ids = new Set();
+		for (const line of this.lines()) {
+			const [extractor, id] = line.split(/\s+/);
+			if (extractor === "youtube" && id) ids.add(id);
+		}
+		return ids;
+	}
+
+	has(videoId) {
+		return this.ids().has(videoId);
+	}
+
+	add(videoId) {
+		if (this.has(videoId)) return;
+		fs.mkdirSync(path.dirname(this.file), { recursive: true });
+		let prefix = "";
+		try {
+			const text = fs.readFileSync(this.file, "utf8");
+			if (text && !text.endsWith("\n")) prefix = "\n";
+		} catch (err) {}
+		fs.appendFileSync(this.file, `${prefix}youtube ${videoId}\n`);
+	}
+
+	/**
+	 * Merge the lines of another archive file into this one
+	 * @param {String} file
+	 * @returns {Number} number of lines added
+	 */
+	importFrom(file) {
+		if (!fs.existsSync(file)) throw new Error(`${file} does not exist`);
+		const other = new Archive(file).lines();
+		const current = this.lines();
+		const known = new Set(current);
+		const added = other.filter((l) => !known.has(l));
+		fs.mkdirSync(path.dirname(this.file), { recursive: true });
+		fs.writeFileSync(this.file, [...current, ...added].map((l) => `${l}\n`).join(""));
+		return added.length;
+	}
+
+	/**
+	 * Write a copy of the archive
+	 * @param {String} file
+	 */
+	exportTo(file) {
+		fs.writeFileSync(file, this.lines().map((l) => `${l}\n`).join(""));
+	}
+}
+
+module.exports = { DEFAULT_ARCHIVE, Archive };
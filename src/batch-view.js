@@ -0,0 +1,124 @@
+import { html, render } from "../node_modules/lit-html/lit-html.js";
+import { constants } from "./constants.js";
+
+let text = ""; // URLs typed, pasted or imported
+let choice; // preference or preset name for rows without one
+let results; // from queueBatch() for the last batch
+let busy = false;
+
+/**
+ * Render the batch panel: a list of URLs, or CSV rows of url, preset and
+ * save location, queued together
+ * @param {Element} container
+ * @param {Object} opts
+ * @param {Object} opts.options 	the app options
+ * @param {Function} opts.setOption 	called with (key, value) to persist an option
+ * @param {Function} opts.request 	returns what to queue rows with, see
+ * 									queueUrl() in service.js
+ * @param {Function} opts.serviceError 	message of an error thrown by the service
+ */
+export function renderBatch(container, opts) {
+	const { options, setOption, request, serviceError } = opts;
+	const update = () => renderBatch(container, opts);
+	const api = window.ytdl;
+	const settings = { ...constants.defaults.batch, ...options.batch };
+	const presets = options.presets || [];
+	const defaultPreset = { ...constants.defaults.defaultPreset, ...options.defaultPreset };
+	if (choice === undefined) choice = defaultPreset.name || "merged";
+
+	const importFile = async () => {
+		try {
+			const imported = await api.importBatch();
+			if (imported !== undefined) text = imported;
+		} catch (err) {
+			results = { error: serviceError(err) };
+		}
+		update();
+	};
+	const queueAll = async () => {
+		busy = true;
+		results = undefined;
+		update();
+		const req = constants.preferences[choice] ? { format: choice } : { preset: choice };
+		try {
+			results = { rows: await api.queueBatch(text, { ...request(), ...req }) };
+		} catch (err) {
+			results = { error: serviceError(err) };
+		}
+		busy = false;
+		update();
+	};
+
+	const summary = () => {
+		if (!results) return "";
+		if (results.error) return html`<div class="merge-row error">${results.error}</div>`;
+		const failed = results.rows.filter((r) => r.error);
+		const queued = results.rows.reduce((n, r) => n + r.queued, 0);
+		return html`<div class="merge-row">
+				Queued ${queued} video${queued === 1 ? "" : "s"} from
+				${results.rows.length - failed.length} of ${results.rows.length} lines
+			</div>
+			${failed.length
+				? html`<table>
+						<tbody>
+							<tr>
+								<th>Line</th>
+								<th>URL</th>
+								<th>Error</th>
+							</tr>
+							${failed.map(
+								(r) => html`<tr>
+									<td>${r.line}</td>
+									<td class="title" title=${r.url}>${r.url}</td>
+									<td class="error">${r.error}</td>
+								</tr>`
+							)}
+						</tbody>
+				  </table>`
+				: ""}`;
+	};
+
+	render(
+		html`<textarea
+				rows="8"
+				placeholder="One URL per line, or CSV rows of url, preset and save location"
+				.value=${text}
+				@input=${(e) => (text = e.target.value)}
+			></textarea>
+			<div class="merge-row">
+				<button @click=${importFile}>Import file…</button>
+				<label>
+					Rows without a preset
+					<select @change=${(e) => (choice = e.target.value)}>
+						${Object.entries(constants.preferences).map(
+							([key, label]) =>
+								html`<option value=${key} .selected=${key === choice}>${label}</option>`
+						)}
+						${presets.map(
+							(p) => html`<option value=${p.name} .selected=${p.name === choice}>${p.name}</option>`
+						)}
+					</select>
+				</label>
+				<label>
+					Open
+					<input
+						type="number"
+						min="1"
+						max="16"
+						.value=${`${settings.concurrency}`}
+						@change=${(e) =>
+							setOption("batch", {
+								...settings,
+								concurrency: Math.min(16, Math.max(1, parseInt(e.target.value, 10) || 1)),
+							})}
+					/>
+					at once
+				</label>
+				<button ?disabled=${busy || !text.trim()} @click=${queueAll}>
+					${busy ? "Queuing…" : "Queue all"}
+				</button>
+			</div>
+			${summary()}`,
+		container
+	);
+}
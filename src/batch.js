@@ -0,0 +1,129 @@
+/**
+ * Batch settings, stored as options.batch. `concurrency` is how many URLs
+ * are opened at once.
+ */
+const DEFAULT_BATCH = { concurrency: 4 };
+
+/**
+ * CSV header names of the columns, lowercase without spaces, - or _
+ */
+const COLUMNS = {
+	url: ["url", "link", "video"],
+	preset: ["preset", "format", "quality"],
+	saveDir: ["savedir", "outputdir", "output", "dir", "directory", "folder", "savelocation"],
+};
+
+const MAX_LINES = 5000;
+
+/**
+ * Split a CSV line into its cells, with "quoted" cells holding commas or ""
+ * @param {String} line
+ * @param {String} separator 	, or ; or a tab
+ */
+function splitCsv(line, separator) {
+	const cells = [];
+	let cell = "";
+	let quoted = false;
+	for (let i = 0; i < line.length; i++) {
+		const c = line[i];
+		if (quoted) {
+			if (c === '"' && line[i + 1] === '"') {
+				cell += '"';
+				i++;
+			} else if (c === '"') {
+				quoted = false;
+			} else {
+				cell += c;
+			}
+		} else if (c === '"' && !cell.trim()) {
+			quoted = true;
+			cell = "";
+		} else if (c === separator) {
+			cells.push(cell.trim());
+			cell = "";
+		} else {
+			cell += c;
+		}
+	}
+	cells.push(cell.trim());
+	return cells;
+}
+
+/**
+ * Where each column is, if the line is a header naming a url column
+ */
+function headerColumns(cells) {
+	const names = cells.map((c) => c.toLowerCase().replace(/[\s_-]/g, ""));
+	const columns = {};
+	for (const [key, aliases] of Object.entries(COLUMNS)) {
+		const i = names.findIndex((n) => aliases.includes(n));
+		if (i >= 0) columns[key] = i;
+	}
+	return columns.url === undefined ? undefined : columns;
+}
+
+/**
+ * Parse pasted or imported URLs: one per line, or CSV rows of url, preset
+ * and save location, in that order unless a header row names the columns.
+ * Blank lines and lines starting with # are ignored. The preset of a row is
+ * the name of a preset or a format like merged, audio or 137+140.
+ * @param {String} text
+ * @returns {Array} rows of { line, url, preset, saveDir, error } where line
+ * 					counts from 1 and error is set for rows that cannot be queued
+ */
+function parseBatch(text) {
+	const lines = `${text || ""}`.replace(/^\uFEFF/, "").split(/\r?\n/);
+	const firstRow = lines.find((l) => l.trim() && !l.trim().startsWith("#")) || "";
+	const separator = ["\t", ";", ","].find((s) => firstRow.includes(s)) || ",";
+	let columns = { url: 0, preset: 1, saveDir: 2 };
+	const rows = [];
+	let header = false;
+
+	lines.forEach((raw, i) => {
+		const text = raw.trim();
+		if (!text || text.startsWith("#")) return;
+		const cells = splitCsv(text, separator);
+		if (!header) {
+			header = true;
+			const named = headerColumns(cells);
+			if (named) {
+				columns = named;
+				return;
+			}
+		}
+		const cell = (key) => (columns[key] === undefined ? "" : cells[columns[key]] || "");
+		const row = { line: i + 1, url: cell("url"), preset: cell("preset"), saveDir: cell("saveDir") };
+		if (rows.length >= MAX_LINES) {
+			row.error = `only ${MAX_LINES} URLs can be queued at once`;
+		} else if (!row.url) {
+			row.error = "no URL";
+		} else if (!/^https?:\/\/\S+$/i.test(row.url)) {
+			row.error = "not a URL";
+		}
+		rows.push(row);
+	});
+	return rows;
+}
+
+/**
+ * Run a task for each item, at most `limit` at a time
+ * @param {Array} items
+ * @param {Number} limit
+ * @param {Function} task 	called with (item, index), returns a promise
+ * @returns {Promise<Array>} what the tasks resolved with, in order
+ */
+async function mapLimit(items, limit, task) {
+	const results = new Array(items.length);
+	let next = 0;
+	const worker = async () => {
+		while (next < items.length) {
+			const i = next++;
+			results[i] = await task(items[i], i);
+		}
+	};
+	const workers = Math.max(1, Math.min(limit || 1, items.length));
+	await Promise.all(Array.from({ length: workers }, worker));
+	return results;
+}
+
+module.exports = { DEFAULT_BATCH, parseBatch, mapLimit };
@@ -0,0 +1,129 @@
+/**
+ * Default chapter settings, stored as options.chapters
+ */
+const DEFAULT_CHAPTERS = { mode: "none" };
+
+/**
+ * What to do with the chapters of a video
+ */
+const CHAPTER_MODES = {
+	none: "Ignore them",
+	embed: "Write them into the file",
+	split: "Split into one file per chapter",
+};
+
+const TIMESTAMP = "((?:\\d{1,2}:)?\\d{1,2}:\\d{2})";
+// "0:00 Intro", "(1:02:03) - Part 2", "1. Intro 0:00"
+const LEADING = new RegExp(`^[\\s\\-*•\\d.]*?\\(?${TIMESTAMP}\\)?\\s*[-–—:|.]*\\s*(.+)$`);
+const TRAILING = new RegExp(`^(.+?)\\s*[-–—:|]*\\s*\\(?${TIMESTAMP}\\)?$`);
+
+function seconds(timestamp) {
+	return timestamp.split(":").reduce((total, part) => total * 60 + parseInt(part, 10), 0);
+}
+
+/**
+ * Give each chapter the start of the next one as its end
+ * @param {Array} chapters 	[{ start, title }] in order
+ * @param {Number} duration 	length of the video in seconds
+ */
+function withEnds(chapters, duration) {
+	return chapters.map((c, i) => ({
+		...c,
+		end: i + 1 < chapters.length ? chapters[i + 1].start : duration,
+	}));
+}
+
+/**
+ * Chapters listed as timestamps in a video description. Like YouTube, this
+ * needs at least two timestamps in increasing order with the first at 0:00.
+ * @param {String} description
+ * @param {Number} duration 	length of the video in seconds
+ * @returns {Array} [{ start, end, title }] in seconds
+ */
+function parseDescription(description, duration) {
+	const chapters = [];
+	for (const line of (description || "").split(/\r?\n/)) {
+		const leading = LEADING.exec(line.trim());
+		const trailing = !leading && TRAILING.exec(line.trim());
+		if (!leading && !trailing) continue;
+		const [start, title] = leading
+			? [seconds(leading[1]), leading[2]]
+			: [seconds(trailing[2]), trailing[1]];
+		const previous = chapters[chapters.length - 1];
+		if (previous && start <= previous.start) continue;
+		if (duration && start >= duration) continue;
+		chapters.push({ start, title: title.trim() });
+	}
+	if (chapters.length < 2 || chapters[0].start !== 0) return [];
+	return withEnds(chapters, duration);
+}
+
+/**
+ * Chapters set by the uploader in the player, if ytdl-core exposes them
+ * @param {Object} info 	result of ytdl.getInfo()
+ * @returns {Array} [{ start, end, title }] in seconds
+ */
+function playerChapters(info) {
+	const duration = parseInt(info.videoDetails.lengthSeconds, 10) || 0;
+	if (Array.isArray(info.videoDetails.chapters) && info.videoDetails.chapters.length) {
+		return withEnds(
+			info.videoDetails.chapters.map((c) => ({ start: c.start_time, title: c.title })),
+			duration
+		);
+	}
+	try {
+		const bar =
+			info.response.playerOverlays.playerOverlayRenderer.decoratedPlayerBarRenderer
+				.decoratedPlayerBarRenderer.playerBar.multiMarkersPlayerBarRenderer;
+		const markers = bar.markersMap.find((m) => m.key === "DESCRIPTION_CHAPTERS") || bar.markersMap[0];
+		const text = (t) => t.simpleText || t.runs.map((r) => r.text).join("");
+		return withEnds(
+			markers.value.chapters.map(({ chapterRenderer: c }) => ({
+				start: c.timeRangeStartMillis / 1000,
+				title: text(c.title),
+			})),
+			duration
+		);
+	} catch (err) {
+		return [];
+	}
+}
+
+/**
+ * Chapters of a video, from the player or else from the description
+ * @param {Object} info 	result of ytdl.getInfo()
+ * @returns {Array} [{ start, end, title }] in seconds
+ */
+function chaptersOf(info) {
+	const chapters = playerChapters(info);
+	if (chapters.length) return chapters;
+	const d = info.videoDetails;
+	return parseDescription(d.description, parseInt(d.lengthSeconds, 10) || 0);
+}
+
+/**
+ * Chapters in ffmpeg's metadata file format, read with -map_chapters
+ * @param {Array} chapters 	from chaptersOf()
+ */
+function ffmetadata(chapters) {
+	const escape = (s) => s.replace(/[=;#\\\n]/g, (c) => `\\${c}`);
+	return [
+		";FFMETADATA1",
+		...chapters.flatMap((c) => [
+			"[CHAPTER]",
+			"TIMEBASE=1/1000",
+			`START=${Math.round(c.start * 1000)}`,
+			`END=${Math.round(c.end * 1000)}`,
+			`title=${escape(c.title)}`,
+		]),
+		"",
+	].join("\n");
+}
+
+module.exports = {
+	DEFAULT_CHAPTERS,
+	CHAPTER_MODES,
+	parseDescription,
+	chaptersOf,
+	ffmetadata,
+};
@@ -0,0 +1,70 @@
+/**
+ * Default clipping settings, stored as options.clip. Accurate clips are
+ * re-encoded so they start on the exact frame, others are stream copied and
+ * start on the keyframe before.
+ */
+const DEFAULT_CLIP = { accurate: false };
+
+/**
+ * Parse a time typed by the user: seconds, m:ss or h:mm:ss, with optional
+ * fractions of a second. Minutes and seconds after a colon take two digits
+ * and stay below 60.
+ * @param {String} str
+ * @returns {Number} seconds, NaN if invalid, undefined if empty
+ */
+function parseTimestamp(str) {
+	const s = `${str || ""}`.trim();
+	if (!s) return undefined;
+	if (!/^(\d+|\d+:(\d{2}:)?\d{2})(\.\d+)?$/.test(s)) return NaN;
+	const [first, ...rest] = s.split(":").map(parseFloat);
+	if (rest.some((part) => part >= 60)) return NaN;
+	return rest.reduce((total, part) => total * 60 + part, first);
+}
+
+/**
+ * Check a range typed by the user and turn it into a job clip
+ * @param {String} start 	empty for the beginning of the video
+ * @param {String} end 	empty for the end of the video
+ * @param {Number} duration 	length of the video in seconds, 0 for live
+ * 							streams and premieres, whose clips need an end
+ * @returns {Object} { clip } with { start, end } in seconds, undefined when
+ * 					both are empty, or { error }
+ */
+function parseClip(start, end, duration) {
+	const from = parseTimestamp(start);
+	const to = parseTimestamp(end);
+	if (from === undefined && to === undefined) return { clip: undefined };
+	if (Number.isNaN(from) || Number.isNaN(to)) return { error: "use seconds, m:ss or h:mm:ss" };
+	// live streams and premieres have no length yet
+	if (to === undefined && !duration) {
+		return { error: "type an end, the length of the video is unknown" };
+	}
+	const clip = { start: from || 0, end: to === undefined ? duration : to };
+	if (clip.end <= clip.start) return { error: "end must be after start" };
+	if (duration && clip.end > duration) return { error: "end is past the end of the video" };
+	return { clip };
+}
+
+/**
+ * A time as 1h02m03s, 12m30s or 12m30.5s, safe in filenames
+ * @param {Number} seconds
+ */
+function timeLabel(seconds) {
+	// rounded before splitting so 59.9996 becomes 1m00s rather than 0m60s
+	const total = Math.round(seconds * 1000) / 1000;
+	const h = Math.floor(total / 3600);
+	const m = Math.floor((total % 3600) / 60);
+	const s = +(total % 60).toFixed(3);
+	const ss = `${s < 10 ? "0" : ""}${s}s`;
+	return h ? `${h}h${`${m}`.padStart(2, "0")}m${ss}` : `${m}m${ss}`;
+}
+
+/**
+ * Range of a clip for filenames and the queue, e.g. 12m30s-15m00s
+ * @param {Object} clip 	{ start, end } in seconds
+ */
+function clipLabel(clip) {
+	return `${timeLabel(clip.start)}-${timeLabel(clip.end)}`;
+}
+
+module.exports = { DEFAULT_CLIP, parseTimestamp, parseClip, clipLabel };
@@ -0,0 +1,9 @@
+/**
+ * Tables and defaults of the download engine, see constants() in service.js.
+ * Filled once at startup by loadConstants().
+ */
+export const constants = {};
+
+export async function loadConstants() {
+	Object.assign(constants, await window.ytdl.constants());
+}
@@ -0,0 +1,104 @@
+const NAME = /^[!#$%&'*+\-.^`|~\w]+$/;
+
+/**
+ * Values holding control characters, which would end the header early
+ */
+const CONTROL = /[\u0000-\u001f\u007f]/;
+
+/**
+ * Whether cookies of a domain are sent to YouTube
+ * @param {String} domain 	as in cookies.txt, like .youtube.com
+ */
+const forYouTube = (domain) => /(^|\.)youtube\.com$/i.test(domain.replace(/^\./, ""));
+
+/**
+ * Read the YouTube cookies of a Netscape cookies.txt file, as exported by
+ * browser extensions or yt-dlp, leaving out expired ones
+ * @param {String} text
+ * @returns {Array} { name, value }
+ */
+function parseCookiesTxt(text) {
+	const now = Date.now() / 1000;
+	const cookies = [];
+	for (const raw of text.split(/\r?\n/)) {
+		// cookies only sent over http are written with this prefix
+		const line = raw.replace(/^#HttpOnly_/, "");
+		if (!line.trim() || line.startsWith("#")) continue;
+		const fields = line.split("\t");
+		if (fields.length < 7) continue;
+		const [domain, , , , expires, name, ...value] = fields;
+		const expiry = parseInt(expires, 10) || 0;
+		if (!forYouTube(domain) || !NAME.test(name) || (expiry && expiry < now)) continue;
+		const joined = value.join("\t").trim();
+		if (!CONTROL.test(joined)) cookies.push({ name, value: joined });
+	}
+	return cookies;
+}
+
+/**
+ * Read a Cookie header, as copied from the network tab of a browser
+ * @param {String} text 	with or without the "Cookie:" prefix
+ * @returns {Array} { name, value }
+ */
+function parseCookieHeader(text) {
+	return text
+		.replace(/^\s*cookie:/i, "")
+		.split(";")
+		.map((pair) => {
+			const i = pair.indexOf("=");
+			return i < 0 ? {} : { name: pair.slice(0, i).trim(), value: pair.slice(i + 1).trim() };
+		})
+		.filter((c) => c.name && NAME.test(c.name) && !CONTROL.test(c.value));
+}
+
+/**
+ * The Cookie header to send to YouTube from a cookies.txt file or a pasted
+ * header
+ * @param {String} text
+ * @returns {Object} { header, count } or { error }
+ */
+function cookieHeader(text) {
+	const netscape =
+		/# Netscape HTTP Cookie/.test(text) || /^[^\t\n]+\t(TRUE|FALSE)\t/im.test(text);
+	const parsed = netscape ? parseCookiesTxt(text) : parseCookieHeader(text);
+	// later cookies of the same name win, like in a browser
+	const byName = new Map(parsed.map((c) => [c.name, c.value]));
+	if (!byName.size) {
+		return { error: netscape ? "no valid cookies for youtube.com" : "no cookies found" };
+	}
+	const header = [...byName].map(([name, value]) => `${name}=${value}`).join("; ");
+	return { header, count: byName.size };
+}
+
+/**
+ * The cookies requests to YouTube carry, shared by getInfo() and downloads
+ */
+class CookieJar {
+	constructor() {
+		this.header = "";
+	}
+
+	set(header) {
+		this.header = header || "";
+	}
+
+	count() {
+		return this.header ? this.header.split("; ").length : 0;
+	}
+
+	/**
+	 * Add the cookies to request options for ytdl-core
+	 * @param {Object} options 	requestOptions, see proxy.js
+	 */
+	requestOptions(options = {}) {
+		if (!this.header) return options;
+		return { ...options, headers: { ...options.headers, cookie: this.header } };
+	}
+}
+
+/**
+ * The jar of the process, filled by index.js and the command line
+ */
+const cookies = new CookieJar();
+
+module.exports = { cookieHeader, cookies };
@@ -0,0 +1,718 @@
+const ytdl = require("ytdl-core");
+const fs = require("fs");
+const path = require("path");
+const {
+	CONTAINERS,
+	AUDIO_CODECS,
+	codecIn,
+	postprocessArgs,
+	cutArgs,
+	canRemux,
+	canEmbedSubtitles,
+	runFfmpeg,
+} = require("./ffmpeg.js");
+const { tagsOf, fetchThumbnail } = require("./metadata.js");
+const { saveSubtitles } = require("./subtitles.js");
+const { chaptersOf, ffmetadata } = require("./chapters.js");
+const { clipLabel } = require("./clip.js");
+const {
+	DEFAULT_TEMPLATE,
+	DEFAULT_CHAPTER_TEMPLATE,
+	templateValues,
+	renderTemplate,
+} = require("./template.js");
+const { PROFILES, defaultProfile, sanitizePath, numbered } = require("./sanitize.js");
+const { Archive } = require("./archive.js");
+const { selectFormats } = require("./presets.js");
+const { fetchInfo } = require("./info-cache.js");
+const { proxies } = require("./proxy.js");
+const { cookies } = require("./cookies.js");
+
+// files claimed by jobs that are running but may not have written anything yet
+const claimed = new Set();
+
+/**
+ * File extension for a format
+ * @param {Object} format 	an entry in info.formats
+ */
+function extensionOf(format) {
+	return format.hasVideo
+		? format.container
+		: format.container === "mp4"
+		? "m4a"
+		: format.container === "webm"
+		? "webm"
+		: "unknown";
+}
+
+/**
+ * Full path a job is saved to, from its filename template, or the path of
+ * one of its chapters from the chapter template. Clips get their range added
+ * to the name.
+ * @param {Object} job 	a queue job with saveDir, template and playlist
+ * @param {Object} info 	result of ytdl.getInfo()
+ * @param {Array} formats 	the entries of info.formats the job downloads
+ * @param {String} ext
+ * @param {String} profile 	a key of PROFILES in sanitize.js
+ * @param {Object} chapter 	{ title, index, count } when splitting by chapter
+ */
+function outputPath(job, info, formats, ext, profile, chapter) {
+	const values = templateValues(info, { formats, ext, playlist: job.playlist, chapter });
+	const template = chapter
+		? job.chapters.template || DEFAULT_CHAPTER_TEMPLATE
+		: job.template || DEFAULT_TEMPLATE;
+	let relative = renderTemplate(template, values);
+	if (job.clip && !chapter) {
+		relative = relative.replace(/(\.[^./]*)?$/, (ext) => ` [${clipLabel(job.clip)}]${ext}`);
+	}
+	return path.join(job.saveDir, ...sanitizePath(relative, profile).split("/"));
+}
+
+/**
+ * Best video-only and audio-only formats to merge into a container,
+ * preferring formats that can be stream copied into it
+ * @param {Array} formats 	info.formats
+ * @param {String} container 	a key of CONTAINERS
+ */
+function bestPair(formats, container) {
+	const c = CONTAINERS[container];
+	const pick = (quality, filter, codecs) => {
+		const copyable = formats.filter((f) => filter(f) && codecIn(f.codecs, codecs));
+		try {
+			return ytdl.chooseFormat(copyable.length ? copyable : formats.filter(filter), {
+				quality,
+			});
+		} catch (err) {
+			return undefined;
+		}
+	};
+	return {
+		video: pick("highestvideo", (f) => f.hasVideo && !f.hasAudio, c.video),
+		audio: pick("highestaudio", (f) => f.hasAudio && !f.hasVideo, c.audio),
+	};
+}
+
+/**
+ * The fields of formats scripts need to pick one
+ * @param {Array} formats 	info.formats
+ */
+function describeFormats(formats) {
+	return formats.map((f) => ({
+		itag: f.itag,
+		container: f.container,
+		hasVideo: f.hasVideo,
+		hasAudio: f.hasAudio,
+		qualityLabel: f.qualityLabel || "",
+		fps: f.fps || 0,
+		bitrate: f.bitrate || 0,
+		audioBitrate: f.audioBitrate || 0,
+		contentLength: parseInt(f.contentLength, 10) || 0,
+	}));
+}
+
+/**
+ * Pick the formats of a job queued with a `preset` { name, rule, container }
+ * (see presets.js) or a `preference` (see PREFERENCES in playlist.js)
+ * instead of an itag
+ * @param {Object} job 	a queue job with url and preset or preference
+ * @param {Object} info 	result of ytdl.getInfo()
+ */
+function pickFormats(job, info) {
+	if (job.preset) {
+		const picked = selectFormats(info.formats, job.preset.rule);
+		if (!picked) throw new Error(`no format matches preset ${job.preset.name}`);
+		if (picked.error) throw new Error(`preset ${job.preset.name}: ${picked.error}`);
+		const { video, audio, format } = picked;
+		if (format) {
+			job.itag = format.itag;
+			job.container = format.container;
+			job.qualityLabel = format.qualityLabel || `${format.audioBitrate || ""}kbps`;
+		} else {
+			job.itag = video.itag;
+			job.audioItag = audio.itag;
+			job.container = job.container || "mkv";
+			job.qualityLabel = `${video.qualityLabel || ""} + ${audio.audioBitrate || ""}kbps`;
+		}
+		job.convert = undefined;
+		return;
+	}
+
+	const choose = (quality, filter) => {
+		try {
+			return ytdl.chooseFormat(info.formats, { quality, filter });
+		} catch (err) {
+			return undefined;
+		}
+	};
+
+	if (job.preference === "merged") {
+		const container = job.container || "mkv";
+		const { video, audio } = bestPair(info.formats, container);
+		if (video && audio) {
+			job.itag = video.itag;
+			job.audioItag = audio.itag;
+			job.container = container;
+			job.qualityLabel = `${video.qualityLabel || ""} + ${audio.audioBitrate || ""}kbps`;
+			return;
+		}
+	}
+	if (job.preference === "audio") {
+		const audio = choose("highestaudio", "audioonly");
+		if (audio) {
+			const converted = job.convert && AUDIO_CODECS[job.convert.codec];
+			job.itag = audio.itag;
+			job.container = converted ? converted.ext : audio.container;
+			job.qualityLabel = `${audio.audioBitrate || ""}kbps`;
+			if (!converted) job.convert = undefined;
+			return;
+		}
+	}
+	const format = choose("highest", "audioandvideo") || choose("highest");
+	if (!format) throw new Error("no downloadable format");
+	job.itag = format.itag;
+	job.container = format.container;
+	job.qualityLabel = format.qualityLabel || "";
+	job.convert = undefined;
+}
+
+/**
+ * Whether a download to a file has started, by any job
+ * @param {String} filename
+ */
+function isTaken(filename) {
+	return (
+		claimed.has(filename) ||
+		fs.existsSync(filename) ||
+		fs.existsSync(`${filename}.part`)
+	);
+}
+
+/**
+ * Pick the formats and output file of a job when it first starts. Jobs that
+ * are already resolved are left alone. Resolves with "skip" when the file
+ * exists and the collision policy says to leave it.
+ * @param {Object} job 	a queue job
+ * @param {Object} info 	result of ytdl.getInfo()
+ * @param {Object} options 	the app options
+ * @param {Function} askCollision 	called with a filename when the policy is
+ * 									"ask", resolves with another policy
+ */
+async function resolveJob(job, info, options, askCollision) {
+	if (job.filename) return undefined;
+	if (!job.itag) pickFormats(job, info);
+	const itags = job.audioItag ? [job.itag, job.audioItag] : [job.itag];
+	const formats = itags.map((itag) => info.formats.find((f) => f.itag === itag));
+	if (formats.some((f) => !f)) throw new Error(`format ${itags.join("+")} is no longer available`);
+
+	const profileName = options.sanitize || defaultProfile();
+	const profile = PROFILES[profileName] || PROFILES[defaultProfile()];
+	let size = formats.reduce((sum, f) => sum + (parseInt(f.contentLength, 10) || 0), 0);
+	if (job.clip && job.duration) size *= (job.clip.end - job.clip.start) / job.duration;
+	if (profile.maxFileSize && size > profile.maxFileSize) {
+		throw new Error(`too large for ${profile.label}`);
+	}
+
+	const converted = job.convert && AUDIO_CODECS[job.convert.codec];
+	const ext = job.audioItag
+		? job.container
+		: converted
+		? converted.ext
+		: extensionOf(formats[0]);
+	const filename = outputPath(job, info, formats, ext, profileName);
+	const free = await applyCollision(filename, profileName, options, askCollision);
+	job.filename = free || filename;
+	return free ? undefined : "skip";
+}
+
+/**
+ * The first of `name`, `name (1)`, `name (2)`... that is not taken
+ * @param {String} filename
+ * @param {String} profileName 	a key of PROFILES
+ * @param {Function} taken 	whether a filename is taken, isTaken() by default
+ */
+function freeName(filename, profileName, taken = isTaken) {
+	let free = filename;
+	for (let n = 1; taken(free); n++) {
+		free = path.join(path.dirname(filename), numbered(path.basename(filename), n, profileName));
+	}
+	return free;
+}
+
+/**
+ * Apply the collision policy of the options to a file about to be written
+ * @param {String} filename
+ * @param {String} profileName 	a key of PROFILES
+ * @param {Object} options 	the app options
+ * @param {Function} askCollision 	see resolveJob()
+ * @returns {Promise<String>} the file to write, or undefined to skip it
+ */
+async function applyCollision(filename, profileName, options, askCollision) {
+	let policy = options.collision || "number";
+	if (isTaken(filename) && policy === "ask" && askCollision) {
+		policy = await askCollision(filename);
+	}
+	if (isTaken(filename) && policy === "skip") return undefined;
+	return policy === "overwrite" ? filename : freeName(filename, profileName);
+}
+
+/**
+ * Read the sidecar describing a partial download, or null if there is no
+ * usable partial file for this format
+ * @param {String} filename 	final path of the download
+ * @param {Object} format 	the format about to be downloaded
+ */
+function readPartial(filename, format) {
+	try {
+		const meta = JSON.parse(fs.readFileSync(`${filename}.part.json`, "utf8"));
+		const size = fs.statSync(`${filename}.part`).size;
+		if (meta.itag !== format.itag) return null;
+		if (`${meta.contentLength}` !== `${format.contentLength}`) return null;
+		// the file on disk is the truth, the sidecar may lag behind
+		return { ...meta, written: size };
+	} catch (err) {
+		return null;
+	}
+}
+
+function writePartial(filename, meta) {
+	try {
+		fs.writeFileSync(`${filename}.part.json`, JSON.stringify(meta));
+	} catch (err) {
+		console.error(err);
+	}
+}
+
+function unlinkQuietly(...files) {
+	for (const f of files) {
+		try {
+			fs.unlinkSync(f);
+		} catch (err) {}
+	}
+}
+
+/**
+ * Whether a job is post-processed by ffmpeg after downloading
+ * @param {Object} job
+ */
+function needsFfmpeg(job) {
+	return !!(
+		job.audioItag ||
+		job.clip ||
+		(job.convert && job.convert.codec !== "original") ||
+		(job.embed && (job.embed.metadata || job.embed.thumbnail) && canRemux(job.filename)) ||
+		embedsSubtitles(job) ||
+		embedsChapters(job)
+	);
+}
+
+/**
+ * Whether chapters get written into the file of a job
+ * @param {Object} job
+ */
+function embedsChapters(job) {
+	return !!(
+		job.chapters &&
+		job.chapters.mode === "embed" &&
+		job.chapterCount &&
+		!job.clip &&
+		(job.audioItag ||
+			(job.convert && job.convert.codec !== "original") ||
+			canRemux(job.filename))
+	);
+}
+
+/**
+ * Whether a job embeds its subtitles rather than saving them next to it
+ * @param {Object} job
+ */
+function embedsSubtitles(job) {
+	return !!(
+		job.subtitles &&
+		job.subtitles.embed &&
+		job.subtitles.tracks.length &&
+		canEmbedSubtitles(job.filename)
+	);
+}
+
+/**
+ * Path of a download without its extension, the base name of its subtitles
+ * @param {String} filename
+ */
+function basenameOf(filename) {
+	return filename.replace(/\.[^./\\]*$/, "");
+}
+
+/**
+ * Formats downloaded by a job and the files they are saved to. Jobs that are
+ * post-processed download each format to a temporary file first.
+ * @param {Object} job
+ */
+function streamFiles(job) {
+	if (!needsFfmpeg(job)) return [{ itag: job.itag, file: job.filename }];
+	const itags = job.audioItag ? [job.itag, job.audioItag] : [job.itag];
+	return itags.map((itag) => ({ itag, file: `${job.filename}.f${itag}` }));
+}
+
+/**
+ * Delete the .part files, sidecars and temporary stream files of a job
+ * @param {Object} job 	a queue job
+ */
+function discardPartial(job) {
+	if (!job.filename) return;
+	for (const { file } of streamFiles(job)) {
+		unlinkQuietly(`${file}.part`, `${file}.part.json`);
+		if (file !== job.filename) unlinkQuietly(file);
+	}
+	unlinkQuietly(
+		`${job.filename}.part`,
+		`${job.filename}.thumb.jpg`,
+		`${job.filename}.thumb.webp`,
+		`${job.filename}.chapters.txt`
+	);
+}
+
+/**
+ * Download a single format to a file, continuing a partial download of the
+ * same format if there is one.
+ *
+ * Data is written to `<filename>.part` next to a `<filename>.part.json`
+ * sidecar holding the itag, content length and bytes written. If both exist
+ * the download continues from the end of the .part file with a byte range
+ * request. The .part file is renamed once complete.
+ * @param {Object} info 	result of ytdl.getInfo()
+ * @param {Object} format 	an entry in info.formats
+ * @param {String} filename
+ * @param {Function} progress 	called with (received, total)
+ * @param {EventEmitter} signal 	emits "abort" with "pause" or "remove"
+ */
+async function downloadFormat(info, format, filename, progress, signal) {
+	const partFile = `${filename}.part`;
+	const partial = format.contentLength && readPartial(filename, format);
+	if (!partial) unlinkQuietly(partFile, `${partFile}.json`);
+	const start = partial ? partial.written : 0;
+	if (partial && start >= format.contentLength) {
+		fs.renameSync(partFile, filename);
+		unlinkQuietly(`${partFile}.json`);
+		return;
+	}
+	const meta = {
+		itag: format.itag,
+		contentLength: format.contentLength,
+		written: start,
+	};
+	writePartial(filename, meta);
+
+	const proxy = proxies.proxyFor(format.url);
+	const requestOptions = cookies.requestOptions(proxies.requestOptions(format.url));
+	await new Promise((resolve, reject) => {
+		const writer = fs.createWriteStream(partFile, { flags: start ? "a" : "w" });
+		const res = ytdl.downloadFromInfo(
+			info,
+			start
+				? { quality: format.itag, range: { start }, requestOptions }
+				: { quality: format.itag, requestOptions }
+		);
+		let lastSaved = Date.now();
+		const onAbort = (reason) => {
+			res.destroy();
+			writer.end(() => {
+				if (reason === "remove") unlinkQuietly(partFile, `${partFile}.json`);
+				else writePartial(filename, meta);
+			});
+		};
+		signal.once("abort", onAbort);
+		writer.on("close", () => signal.removeListener("abort", onAbort));
+		res.on("progress", (chunkSize, received, total) => {
+			meta.written = start + received;
+			progress(start + received, start + total);
+			if (Date.now() - lastSaved > 1000) {
+				lastSaved = Date.now();
+				writePartial(filename, meta);
+			}
+		});
+		res.on("error", (err) => {
+			// the retry goes through the next proxy of the pool
+			proxies.failed(err, proxy);
+			writer.end(() => writePartial(filename, meta));
+			reject(err);
+		});
+		writer.on("error", reject);
+		writer.on("finish", () => {
+			if (signal.aborted) return resolve();
+			if (meta.contentLength && meta.written < meta.contentLength) {
+				writePartial(filename, meta);
+				return reject(new Error("download ended early"));
+			}
+			fs.rename(partFile, filename, (err) => {
+				if (err) return reject(err);
+				unlinkQuietly(`${partFile}.json`);
+				resolve();
+			});
+		});
+		res.pipe(writer);
+	});
+}
+
+/**
+ * Download one queued job. Used as the worker of DownloadQueue.
+ *
+ * Jobs with an `audioItag` download the video format `itag` and the audio
+ * format `audioItag` side by side, then mux them with ffmpeg into
+ * `job.container`. Progress covers both streams. Jobs with a `convert`
+ * setting are transcoded with ffmpeg once downloaded, and jobs with an
+ * `embed` setting get the video's metadata and thumbnail written into them.
+ * Subtitle tracks listed in `subtitles` are saved next to the file, or
+ * embedded into it when `subtitles.embed` is set and the container allows.
+ * Chapters are written into the file or used to split it according to
+ * `chapters.mode`. Jobs with a `clip` { start, end, accurate } only keep
+ * that range, read by ffmpeg straight from the format URLs unless cookies
+ * are set, which ffmpeg could only get on its command line, visible to
+ * other users. The streams are then downloaded and clipped locally.
+ * Videos in the download archive are skipped unless the job has
+ * `ignoreArchive` set, and finished videos are added to it.
+ * @param {Object} job 	a queue job with url, itag and filename
+ * @param {Function} progress 	called with (received, total)
+ * @param {EventEmitter} signal 	emits "abort" with "pause" or "remove"
+ * @param {Object} options 	the app options
+ * @param {Object} hooks 	{ askCollision, getInfo } see resolveJob(), getInfo
+ * 							is fetchInfo() of info-cache.js unless info is cached,
+ * 							called with (url, fresh) where fresh asks not to use
+ * 							cached info
+ */
+async function downloadJob(job, progress, signal, options = {}, hooks = {}) {
+	// format urls expire, so retried, resumed and restored jobs need fresh
+	// info rather than the urls that may have just failed
+	const fresh = !!job.started;
+	job.started = true;
+	const info = await (hooks.getInfo || fetchInfo)(job.url, fresh);
+	if (signal.aborted) {
+		if (signal.reason === "remove") discardPartial(job);
+		return;
+	}
+	job.videoId = info.videoDetails.videoId;
+	job.duration = parseInt(info.videoDetails.lengthSeconds, 10) || 0;
+	job.chapterCount = chaptersOf(info).length;
+
+	const archive =
+		options.archive && options.archive.enabled && options.archive.file
+			? new Archive(options.archive.file)
+			: undefined;
+	if (archive && !job.ignoreArchive && archive.has(job.videoId)) {
+		job.skipped = "archive";
+		return;
+	}
+	if ((await resolveJob(job, info, options, hooks.askCollision)) === "skip") {
+		job.skipped = "exists";
+		return;
+	}
+	if (signal.aborted) return;
+	claimed.add(job.filename);
+	try {
+		// jobs paused or failed while splitting keep their complete file
+		if (!job.downloaded || !fs.existsSync(job.filename)) {
+			await downloadStreams(job, info, progress, signal, options);
+			job.downloaded = !signal.aborted;
+		}
+		const split = job.chapters && job.chapters.mode === "split" && job.chapterCount && !job.clip;
+		if (split && !signal.aborted) {
+			await splitChapters(job, info, progress, signal, options, hooks.askCollision);
+		}
+	} finally {
+		claimed.delete(job.filename);
+	}
+	if (archive && !signal.aborted) archive.add(job.videoId);
+}
+
+/**
+ * Download the streams of a resolved job and post-process them
+ * @param {Object} job 	a queue job with its formats and filename picked
+ * @param {Object} info 	result of ytdl.getInfo()
+ * @param {Function} progress 	called with (received, total)
+ * @param {EventEmitter} signal 	emits "abort" with "pause" or "remove"
+ * @param {Object} options 	the app options
+ */
+async function downloadStreams(job, info, progress, signal, options) {
+	const formatOf = (itag) => {
+		const format = info.formats.find((f) => f.itag === itag);
+		if (!format) throw new Error(`format ${itag} is no longer available`);
+		return format;
+	};
+
+	fs.mkdirSync(path.dirname(job.filename), { recursive: true });
+	job.stage = "downloading";
+	// ffmpeg only fetches the part of a clip it needs
+	const remote = job.clip && !cookies.header;
+	const streams = streamFiles(job).map((s) => {
+		const format = formatOf(s.itag);
+		const file = remote ? format.url : s.file;
+		return { ...s, file, format, received: 0, total: parseInt(format.contentLength, 10) || 0 };
+	});
+	// checked first so a proxy ffmpeg cannot use fails before anything is fetched
+	const env = remote ? proxies.ffmpegEnv(streams[0].format.url) : undefined;
+	const report = () => {
+		let received = 0;
+		let total = 0;
+		for (const s of streams) {
+			received += s.received;
+			total += s.total;
+		}
+		progress(received, total);
+	};
+	await Promise.all(
+		(remote ? [] : streams).map((s) => {
+			// temporary files are only renamed into place once complete
+			if (needsFfmpeg(job) && fs.existsSync(s.file)) {
+				s.received = s.total;
+				return undefined;
+			}
+			return downloadFormat(
+				info,
+				s.format,
+				s.file,
+				(received, total) => {
+					s.received = received;
+					s.total = total;
+					report();
+				},
+				signal
+			);
+		})
+	);
+	if (signal.aborted) {
+		if (signal.reason === "remove") discardPartial(job);
+		return;
+	}
+	let subtitles = [];
+	if (job.subtitles && job.subtitles.tracks.length) {
+		job.stage = "subtitles";
+		progress(0, 0);
+		subtitles = await saveSubtitles(info, job.subtitles, basenameOf(job.filename));
+	}
+	if (!needsFfmpeg(job)) return;
+
+	job.stage = job.clip
+		? "clipping"
+		: job.audioItag
+		? "merging"
+		: job.convert
+		? "converting"
+		: "tagging";
+	progress(0, 0);
+	let cover;
+	if (job.embed && job.embed.thumbnail) {
+		try {
+			cover = await fetchThumbnail(info, job.filename);
+		} catch (err) {
+			// a missing cover is not worth failing the download for
+			console.error(err);
+		}
+	}
+	let chapterFile;
+	if (embedsChapters(job)) {
+		chapterFile = `${job.filename}.chapters.txt`;
+		fs.writeFileSync(chapterFile, ffmetadata(chaptersOf(info)));
+	}
+	const partFile = `${job.filename}.part`;
+	const args = postprocessArgs({
+		streams,
+		container: job.container,
+		convert: job.convert,
+		tags: job.embed && job.embed.metadata ? tagsOf(info) : undefined,
+		cover,
+		subtitles: embedsSubtitles(job) ? subtitles : undefined,
+		chapters: chapterFile,
+		clip: job.clip,
+		output: partFile,
+	});
+	try {
+		await runFfmpeg(options.ffmpegPath, args, {
+			signal,
+			env,
+			duration: job.clip
+				? job.clip.end - job.clip.start
+				: parseInt(info.videoDetails.lengthSeconds, 10),
+			progress,
+		});
+	} finally {
+		if (cover) unlinkQuietly(cover.file);
+		if (embedsSubtitles(job)) unlinkQuietly(...subtitles.map((sub) => sub.file));
+		if (chapterFile) unlinkQuietly(chapterFile);
+	}
+	if (signal.aborted) {
+		unlinkQuietly(partFile);
+		if (signal.reason === "remove") discardPartial(job);
+		return;
+	}
+	fs.renameSync(partFile, job.filename);
+	if (!remote) for (const s of streams) unlinkQuietly(s.file);
+}
+
+/**
+ * Cut the finished file of a job into one file per chapter, named by the
+ * chapter template. The full file is kept. Chapter files follow the
+ * collision policy, and chapters of the same name are numbered. The files
+ * written are listed in `job.chapterFiles`, "" for skipped chapters, so a
+ * paused job continues with the next chapter.
+ * @param {Object} job 	a queue job whose file is complete
+ * @param {Object} info 	result of ytdl.getInfo()
+ * @param {Function} progress 	called with (received, total)
+ * @param {EventEmitter} signal 	emits "abort" with "pause" or "remove"
+ * @param {Object} options 	the app options
+ * @param {Function} askCollision 	see resolveJob()
+ */
+async function splitChapters(job, info, progress, signal, options, askCollision) {
+	const chapters = chaptersOf(info);
+	const itags = job.audioItag ? [job.itag, job.audioItag] : [job.itag];
+	const formats = itags.map((itag) => info.formats.find((f) => f.itag === itag));
+	const ext = path.extname(job.filename).slice(1);
+	const profile = options.sanitize || defaultProfile();
+	job.stage = "splitting";
+	job.chapterFiles = job.chapterFiles || [];
+	for (const [i, chapter] of chapters.entries()) {
+		if (i < job.chapterFiles.length) continue;
+		const written = new Set(job.chapterFiles);
+		const name = outputPath(job, info, formats, ext, profile, {
+			title: chapter.title,
+			index: i + 1,
+			count: chapters.length,
+		});
+		const file = await applyCollision(
+			freeName(name, profile, (f) => written.has(f)),
+			profile,
+			options,
+			askCollision
+		);
+		if (signal.aborted) return;
+		if (!file) {
+			job.chapterFiles.push("");
+			continue;
+		}
+		fs.mkdirSync(path.dirname(file), { recursive: true });
+		claimed.add(file);
+		try {
+			const args = cutArgs(job.filename, chapter.start, chapter.end, `${file}.part`);
+			await runFfmpeg(options.ffmpegPath, args, {
+				signal,
+				duration: chapter.end - chapter.start,
+				progress: (done) => progress(chapter.start + done, job.duration),
+			});
+			if (signal.aborted) {
+				unlinkQuietly(`${file}.part`);
+				return;
+			}
+			fs.renameSync(`${file}.part`, file);
+		} finally {
+			claimed.delete(file);
+		}
+		job.chapterFiles.push(file);
+	}
+}
+
+module.exports = {
+	extensionOf,
+	outputPath,
+	bestPair,
+	describeFormats,
+	downloadJob,
+	discardPartial,
+};
@@ -0,0 +1,333 @@
+const { spawn } = require("child_process");
+const { SUBTITLE_FORMATS } = require("./subtitles.js");
+
+/**
+ * Containers a merged download can be saved as, with the ffmpeg muxer name
+ * and the codecs that can be stream copied into them
+ */
+const CONTAINERS = {
+	mp4: {
+		muxer: "mp4",
+		video: ["avc1", "av01", "vp9", "vp09", "hev1", "hvc1"],
+		audio: ["mp4a", "opus", "mp3", "flac"],
+		videoEncoder: ["-c:v", "libx264", "-crf", "20", "-preset", "medium"],
+		audioEncoder: ["-c:a", "aac", "-b:a", "192k"],
+	},
+	mkv: {
+		muxer: "matroska",
+		video: ["avc1", "av01", "vp8", "vp9", "vp09", "hev1", "hvc1"],
+		audio: ["mp4a", "opus", "vorbis", "mp3", "flac"],
+		videoEncoder: ["-c:v", "libx264", "-crf", "20", "-preset", "medium"],
+		audioEncoder: ["-c:a", "aac", "-b:a", "192k"],
+	},
+	webm: {
+		muxer: "webm",
+		video: ["vp8", "vp9", "vp09", "av01"],
+		audio: ["opus", "vorbis"],
+		videoEncoder: ["-c:v", "libvpx-vp9", "-crf", "32", "-b:v", "0"],
+		audioEncoder: ["-c:a", "libopus", "-b:a", "160k"],
+	},
+};
+
+/**
+ * Audio formats downloads can be converted to. `quality` is the setting the
+ * encoder is tuned with: a VBR level for mp3, a bitrate in kbps otherwise.
+ */
+const AUDIO_CODECS = {
+	mp3: {
+		label: "MP3 (VBR)",
+		ext: "mp3",
+		muxer: "mp3",
+		quality: "vbr",
+		args: (s) => ["-c:a", "libmp3lame", "-q:a", `${s.mp3Quality}`],
+	},
+	opus: {
+		label: "Opus",
+		ext: "opus",
+		muxer: "ogg",
+		quality: "bitrate",
+		args: (s) => ["-c:a", "libopus", "-b:a", `${s.bitrate}k`],
+	},
+	flac: {
+		label: "FLAC",
+		ext: "flac",
+		muxer: "flac",
+		quality: "lossless",
+		args: () => ["-c:a", "flac"],
+	},
+	m4a: {
+		label: "M4A (AAC)",
+		ext: "m4a",
+		muxer: "ipod",
+		quality: "bitrate",
+		args: (s) => ["-c:a", "aac", "-b:a", `${s.bitrate}k`],
+	},
+};
+
+/**
+ * Default audio conversion, stored as options.convert. "original" keeps the
+ * downloaded stream as is.
+ */
+const DEFAULT_CONVERT = { codec: "original", mp3Quality: 2, bitrate: 160 };
+
+/**
+ * Whether a codecs string such as `avc1.640028` is in a list of codec names
+ */
+function codecIn(codecs, list) {
+	const name = `${codecs || ""}`.split(",")[0].trim().split(".")[0];
+	return list.includes(name);
+}
+
+/**
+ * ffmpeg muxers of files that are saved as downloaded, by extension
+ */
+const MUXERS = { mp4: "mp4", m4a: "ipod", webm: "webm", mp3: "mp3" };
+
+/**
+ * How muxers store cover art: as an attached picture stream, as a file
+ * attachment, or not at all
+ */
+const COVER_SUPPORT = {
+	mp4: "picture",
+	ipod: "picture",
+	mp3: "picture",
+	flac: "picture",
+	matroska: "attachment",
+};
+
+/**
+ * ffmpeg arguments producing the final file of a job from its downloaded
+ * streams. Two streams are muxed into `container`, stream copying when the
+ * container supports their codecs. A single stream is transcoded if
+ * `convert` asks for it and remuxed as is otherwise. Streams may be URLs,
+ * which ffmpeg reads with range requests, when clipping.
+ * @param {Object} opts
+ * @param {Array} opts.streams 	[{ file, format }], the video first when merging
+ * @param {String} opts.container 	a key of CONTAINERS, when merging
+ * @param {Object} opts.convert 	audio conversion, see DEFAULT_CONVERT
+ * @param {Object} opts.tags 	metadata to write, as ffmpeg metadata keys
+ * @param {Object} opts.cover 	{ file, mimetype } of an image to embed
+ * @param {Array} opts.subtitles 	[{ file, language, format }] to embed, ignored
+ * 									if the muxer cannot hold them
+ * @param {String} opts.chapters 	ffmetadata file with the chapters to write
+ * @param {Object} opts.clip 	{ start, end, accurate } to keep only part of the
+ * 							streams, re-encoding them when accurate
+ * @param {String} opts.output 	written with the muxer of its extension,
+ * 								ignoring a trailing .part
+ */
+function postprocessArgs({
+	streams,
+	container,
+	convert,
+	tags,
+	cover,
+	subtitles,
+	chapters,
+	clip,
+	output,
+}) {
+	// inputs are [file, seek options], seeking the streams and subtitles of clips
+	const seek = clip ? ["-ss", `${clip.start}`, "-t", `${clip.end - clip.start}`] : [];
+	const inputs = streams.map((s) => [s.file, seek]);
+	const copy = (codecs, list) => !(clip && clip.accurate) && codecIn(codecs, list);
+	const args = [];
+
+	let muxer;
+	let videoStreams = 0;
+	if (streams.length === 2) {
+		const c = CONTAINERS[container];
+		const [video, audio] = streams;
+		muxer = c.muxer;
+		videoStreams = 1;
+		args.push(
+			"-map",
+			"0:v:0",
+			"-map",
+			"1:a:0",
+			...(copy(video.format.codecs, c.video) ? ["-c:v", "copy"] : c.videoEncoder),
+			...(copy(audio.format.codecs, c.audio) ? ["-c:a", "copy"] : c.audioEncoder)
+		);
+	} else if (convert && AUDIO_CODECS[convert.codec]) {
+		const codec = AUDIO_CODECS[convert.codec];
+		muxer = codec.muxer;
+		args.push("-map", "0:a:0", ...codec.args({ ...DEFAULT_CONVERT, ...convert }));
+	} else if (clip && clip.accurate) {
+		const c = CONTAINERS[extensionOf(output)] || CONTAINERS.mp4;
+		muxer = muxerOf(output);
+		videoStreams = streams[0].format.hasVideo ? 1 : 0;
+		args.push("-map", "0", ...(videoStreams ? c.videoEncoder : []), ...c.audioEncoder);
+	} else {
+		muxer = muxerOf(output);
+		videoStreams = streams[0].format.hasVideo ? 1 : 0;
+		args.push("-map", "0", "-c", "copy");
+	}
+
+	if (cover && COVER_SUPPORT[muxer] === "picture") {
+		inputs.push([cover.file, []]);
+		args.push(
+			"-map",
+			`${inputs.length - 1}:0`,
+			`-c:v:${videoStreams}`,
+			"mjpeg",
+			`-disposition:v:${videoStreams}`,
+			"attached_pic"
+		);
+		if (muxer === "mp3") args.push("-id3v2_version", "3");
+	} else if (cover && COVER_SUPPORT[muxer] === "attachment") {
+		args.push(
+			"-attach",
+			cover.file,
+			"-metadata:s:t",
+			`mimetype=${cover.mimetype}`,
+			"-metadata:s:t",
+			`filename=cover.${cover.mimetype.split("/")[1]}`
+		);
+	}
+	const subtitleCodecs = (subtitles || []).map((sub) => SUBTITLE_FORMATS[sub.format].codecs[muxer]);
+	if (subtitles && subtitleCodecs.every((c) => c)) {
+		subtitles.forEach((sub, i) => {
+			inputs.push([sub.file, seek]);
+			args.push(
+				"-map",
+				`${inputs.length - 1}:0`,
+				`-c:s:${i}`,
+				subtitleCodecs[i],
+				`-metadata:s:s:${i}`,
+				`language=${sub.language}`
+			);
+		});
+	}
+	if (chapters) {
+		inputs.push([chapters, []]);
+		args.push("-map_chapters", `${inputs.length - 1}`);
+	}
+	for (const [key, value] of Object.entries(tags || {})) {
+		args.push("-metadata", `${key}=${value}`);
+	}
+	args.push("-f", muxer, output);
+	return ["-y", ...inputs.flatMap(([file, options]) => [...options, "-i", file]), ...args];
+}
+
+/**
+ * ffmpeg arguments copying the part of a file between two times into
+ * another file. Cuts land on the nearest keyframes.
+ * @param {String} input
+ * @param {Number} start 	in seconds
+ * @param {Number} end 	in seconds
+ * @param {String} output 	written with the muxer of its extension
+ */
+function cutArgs(input, start, end, output) {
+	return [
+		"-y",
+		"-ss",
+		`${start}`,
+		"-t",
+		`${end - start}`,
+		"-i",
+		input,
+		"-map",
+		"0",
+		"-c",
+		"copy",
+		"-map_chapters",
+		"-1",
+		"-f",
+		muxerOf(output),
+		output,
+	];
+}
+
+/**
+ * ffmpeg muxer of any file this app saves, by its extension
+ * @param {String} filename
+ */
+function muxerOf(filename) {
+	const ext = extensionOf(filename);
+	if (CONTAINERS[ext]) return CONTAINERS[ext].muxer;
+	const codec = Object.values(AUDIO_CODECS).find((c) => c.ext === ext);
+	return codec ? codec.muxer : MUXERS[ext] || ext;
+}
+
+/**
+ * Extension of a file, ignoring a trailing .part
+ */
+function extensionOf(filename) {
+	return filename.replace(/\.part$/, "").split(".").pop();
+}
+
+/**
+ * Whether a downloaded file can be remuxed by postprocessArgs() as is
+ * @param {String} filename
+ */
+function canRemux(filename) {
+	return !!MUXERS[extensionOf(filename)];
+}
+
+/**
+ * Whether subtitle streams can be embedded into a file of this name
+ * @param {String} filename
+ */
+function canEmbedSubtitles(filename) {
+	return ["mp4", "mkv", "webm"].includes(extensionOf(filename));
+}
+
+/**
+ * Parse an ffmpeg `HH:MM:SS.ss` timestamp into seconds
+ */
+function parseTime(str) {
+	const [h, m, s] = str.split(":").map(parseFloat);
+	return h * 3600 + m * 60 + s;
+}
+
+/**
+ * Run ffmpeg to completion
+ * @param {String} ffmpegPath 	the ffmpeg binary, "ffmpeg" to search the PATH
+ * @param {Array} args
+ * @param {Object} opts 	{ signal, duration, progress, env } where progress is
+ * 							called with seconds processed out of duration and
+ * 							env adds environment variables
+ */
+function runFfmpeg(ffmpegPath, args, { signal, duration, progress, env } = {}) {
+	return new Promise((resolve, reject) => {
+		const proc = spawn(ffmpegPath || "ffmpeg", ["-hide_banner", ...args], {
+			windowsHide: true,
+			env: { ...process.env, ...env },
+		});
+		let log = "";
+		const onAbort = () => proc.kill();
+		if (signal) signal.once("abort", onAbort);
+
+		proc.stderr.setEncoding("utf8");
+		proc.stderr.on("data", (data) => {
+			log = (log + data).slice(-4096);
+			const m = /time=(\d+:\d+:[\d.]+)/.exec(data);
+			if (m && progress && duration) progress(parseTime(m[1]), duration);
+		});
+		proc.on("error", (err) => {
+			if (signal) signal.removeListener("abort", onAbort);
+			reject(
+				err.code === "ENOENT"
+					? new Error(`ffmpeg not found at "${ffmpegPath || "ffmpeg"}"`)
+					: err
+			);
+		});
+		proc.on("close", (code) => {
+			if (signal) signal.removeListener("abort", onAbort);
+			if (code === 0 || (signal && signal.aborted)) return resolve();
+			const lines = log.trim().split("\n");
+			reject(new Error(`ffmpeg exited with ${code}: ${lines[lines.length - 1]}`));
+		});
+	});
+}
+
+module.exports = {
+	CONTAINERS,
+	AUDIO_CODECS,
+	DEFAULT_CONVERT,
+	codecIn,
+	postprocessArgs,
+	cutArgs,
+	canRemux,
+	canEmbedSubtitles,
+	runFfmpeg,
+};
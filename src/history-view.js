@@ -0,0 +1,90 @@
+import { html, render } from "../node_modules/lit-html/lit-html.js";
+
+let query = "";
+let outcome = "";
+
+/**
+ * Display a duration in seconds as h:mm:ss
+ * @param {Number} seconds
+ */
+export function displayDuration(seconds) {
+	if (!seconds) return "";
+	const h = Math.floor(seconds / 3600);
+	const m = Math.floor((seconds % 3600) / 60);
+	const s = `${seconds % 60}`.padStart(2, "0");
+	return h ? `${h}:${`${m}`.padStart(2, "0")}:${s}` : `${m}:${s}`;
+}
+
+/**
+ * Render the download history matching the search
+ * @param {Element} container
+ * @param {Object} actions 	{ search(query, outcome), requeue(entry), clear(),
+ * 							displaySize(size) } where search resolves with
+ * 							{ entries, total } from the download service
+ */
+export async function renderHistory(container, actions) {
+	const update = () => renderHistory(container, actions);
+	const { entries, total } = await actions.search(query, outcome);
+
+	render(
+		html`<div class="merge-row">
+				<input
+					type="search"
+					placeholder="Search title, URL, id or path"
+					.value=${query}
+					@input=${(e) => {
+						query = e.target.value;
+						update();
+					}}
+				/>
+				<select
+					@change=${(e) => {
+						outcome = e.target.value;
+						update();
+					}}
+				>
+					<option value="">All</option>
+					<option value="done">Done</option>
+					<option value="failed">Failed</option>
+					<option value="skipped">Skipped</option>
+				</select>
+				<button @click=${() => actions.clear()}>Clear history</button>
+			</div>
+			<table>
+				<tbody>
+					<tr>
+						<th>Date</th>
+						<th>Title</th>
+						<th>itag</th>
+						<th>Size</th>
+						<th>Length</th>
+						<th>Outcome</th>
+						<th></th>
+					</tr>
+					${entries.map(
+						(e) => html`<tr>
+							<td>${new Date(e.time).toLocaleString()}</td>
+							<td class="title" title=${e.path || e.url}>${e.title}</td>
+							<td>${e.itag}</td>
+							<td>${actions.displaySize(e.size)}</td>
+							<td>${displayDuration(e.duration)}</td>
+							<td title=${e.error}>${e.outcome}</td>
+							<td>
+								${e.exists
+									? html`<button @click=${() => window.ytdl.openPath(e.path)}>Open</button>
+											<button @click=${() => window.ytdl.showItemInFolder(e.path)}>
+												Show
+											</button>`
+									: ""}
+								<button @click=${() => actions.requeue(e)}>Queue again</button>
+							</td>
+						</tr>`
+					)}
+				</tbody>
+			</table>
+			${total > entries.length
+				? html`<p>Showing ${entries.length} of ${total} entries</p>`
+				: ""}`,
+		container
+	);
+}
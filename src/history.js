@@ -0,0 +1,108 @@
+const fs = require("fs");
+const path = require("path");
+
+/**
+ * Fields of a queue job needed to queue it again
+ */
+const JOB_FIELDS = [
+	"url",
+	"title",
+	"itag",
+	"audioItag",
+	"preference",
+	"preset",
+	"container",
+	"qualityLabel",
+	"convert",
+	"embed",
+	"subtitles",
+	"chapters",
+	"clip",
+	"template",
+	"saveDir",
+	"playlist",
+	"ignoreArchive",
+];
+
+/**
+ * History entry of a finished job
+ * @param {Object} job 	a queue job that is done or failed
+ * @param {Number} size 	size of the saved file in bytes
+ */
+function historyRecord(job, size) {
+	const spec = {};
+	for (const key of JOB_FIELDS) {
+		if (job[key] !== undefined) spec[key] = job[key];
+	}
+	return {
+		time: Date.now(),
+		url: job.url,
+		videoId: job.videoId || "",
+		title: job.title,
+		itag: job.audioItag ? `${job.itag}+${job.audioItag}` : `${job.itag || ""}`,
+		path: job.filename || "",
+		size,
+		duration: job.duration || 0,
+		outcome: job.state === "done" ? (job.skipped ? "skipped" : "done") : "failed",
+		error: job.error || "",
+		job: spec,
+	};
+}
+
+/**
+ * Download history stored as one JSON object per line
+ */
+class History {
+	constructor(file) {
+		this.file = file;
+	}
+
+	/**
+	 * Every entry, oldest first. Lines that cannot be parsed are skipped.
+	 */
+	load() {
+		let text = "";
+		try {
+			text = fs.readFileSync(this.file, "utf8");
+		} catch (err) {
+			return [];
+		}
+		const entries = [];
+		for (const line of text.split("\n")) {
+			if (!line.trim()) continue;
+			try {
+				entries.push(JSON.parse(line));
+			} catch (err) {}
+		}
+		return entries;
+	}
+
+	append(record) {
+		fs.mkdirSync(path.dirname(this.file), { recursive: true });
+		fs.appendFileSync(this.file, `${JSON.stringify(record)}\n`);
+	}
+
+	clear() {
+		fs.writeFileSync(this.file, "");
+	}
+}
+
+/**
+ * Entries matching a search and an outcome filter, newest first
+ * @param {Array} entries 	from History.load()
+ * @param {String} query 	matched against title, url, video id and path
+ * @param {String} outcome 	"done", "failed", "skipped" or "" for all
+ */
+function searchHistory(entries, query, outcome) {
+	const q = query.trim().toLowerCase();
+	return entries
+		.filter(
+			(e) =>
+				(!outcome || e.outcome === outcome) &&
+				(!q ||
+					[e.title, e.url, e.videoId, e.path].some((f) => `${f || ""}`.toLowerCase().includes(q)))
+		)
+		.reverse();
+}
+
+module.exports = { History, historyRecord, searchHistory };
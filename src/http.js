@@ -0,0 +1,66 @@
+const https = require("https");
+const fs = require("fs");
+const { proxies } = require("./proxy.js");
+
+/**
+ * GET a URL over https through the proxy, if one is set, following
+ * redirects. Resolves with the response.
+ * @param {String} url
+ */
+function request(url, redirects = 5) {
+	return proxies.attempt(url, (options) => get(url, options, redirects));
+}
+
+function get(url, options, redirects) {
+	return new Promise((resolve, reject) => {
+		https
+			.get(url, options, (res) => {
+				if (res.statusCode >= 300 && res.statusCode < 400 && res.headers.location) {
+					res.resume();
+					if (!redirects) return reject(new Error("too many redirects"));
+					return resolve(request(new URL(res.headers.location, url).href, redirects - 1));
+				}
+				if (res.statusCode !== 200) {
+					res.resume();
+					const err = new Error(`Status code: ${res.statusCode}`);
+					err.statusCode = res.statusCode;
+					return reject(err);
+				}
+				resolve(res);
+			})
+			.on("error", reject);
+	});
+}
+
+/**
+ * Download a URL as text
+ * @param {String} url
+ */
+async function getText(url) {
+	const res = await request(url);
+	return new Promise((resolve, reject) => {
+		let body = "";
+		res.setEncoding("utf8");
+		res.on("data", (chunk) => (body += chunk));
+		res.on("end", () => resolve(body));
+		res.on("error", reject);
+	});
+}
+
+/**
+ * Download a URL to a file
+ * @param {String} url
+ * @param {String} file
+ */
+async function fetchToFile(url, file) {
+	const res = await request(url);
+	return new Promise((resolve, reject) => {
+		const writer = fs.createWriteStream(file);
+		res.pipe(writer);
+		writer.on("finish", resolve);
+		writer.on("error", reject);
+		res.on("error", reject);
+	});
+}
+
+module.exports = { request, getText, fetchToFile };
@@ -1,6 +1,27 @@
-const { app, BrowserWindow, Menu, dialog, ipcMain } = require("electron");
+const {
+	app,
+	BrowserWindow,
+	Menu,
+	clipboard,
+	dialog,
+	ipcMain,
+	safeStorage,
+	shell,
+} = require("electron");
 const fs = require("fs");
 const path = require("path");
+const { loadOptions, checkOption } = require("./options");
+const { History } = require("./history");
+const { Archive } = require("./archive");
+const { DownloadService } = require("./service");
+const { PROTOCOL, linkFromArgs, parseLink } = require("./links");
+const { RpcServer, newToken } = require("./rpc");
+const { Subscriptions } = require("./subscriptions");
+const { InfoCache } = require("./info-cache");
+const { proxies } = require("./proxy");
+const { cookieHeader, cookies } = require("./cookies");
+const { saveSecret, loadSecret, removeSecret } = require("./secrets");
+const { REQUESTS, SERVICE_REQUESTS, EVENTS } = require("./api");
 
 // Handle creating/removing shortcuts on Windows when installing/uninstalling.
 if (require("electron-squirrel-startup")) {
@@ -8,28 +29,162 @@ if (require("electron-squirrel-startup")) {
 	app.quit();
 }
 
+// later launches hand their link to the running app instead of opening a
+// second window that would overwrite config.json on close
+if (!app.requestSingleInstanceLock()) app.exit();
+
 let options;
+let service;
+let rpc;
+let subscriptions;
+let proxyPassword = ""; // kept out of config.json, see storeProxyPassword()
+let pendingLink; // opened before the window was ready for it
+let linkTarget; // the webContents that takes opened links
 
-const createWindow = () => {
-	const configLocation = app.getPath("userData");
-	const configFile = path.join(configLocation, "config.json");
-	try {
-		options = JSON.parse(fs.readFileSync(configFile, "utf8"));
-	} catch (e) {
-		options = { width: 880, height: 845 };
+const configLocation = app.getPath("userData");
+const configFile = path.join(configLocation, "config.json");
+const queueFile = path.join(configLocation, "queue.json");
+const history = new History(path.join(configLocation, "history.jsonl"));
+const cookieFile = path.join(configLocation, "cookies.bin");
+const proxyPasswordFile = path.join(configLocation, "proxy-password.bin");
+const MAX_BATCH_FILE = 10 << 20;
+const infoCache = new InfoCache(path.join(configLocation, "info-cache"), () => options.infoCache);
+
+/**
+ * Ask which collision policy to use for a file that exists
+ * @param {String} filename
+ */
+const askCollision = (filename) => {
+	const window = BrowserWindow.getFocusedWindow() || BrowserWindow.getAllWindows()[0];
+	if (!window) return Promise.resolve("number");
+	return dialog
+		.showMessageBox(window, {
+			type: "question",
+			title: "File already exists",
+			message: `${path.basename(filename)} already exists in ${path.dirname(filename)}`,
+			buttons: ["Keep both", "Skip", "Overwrite"],
+			defaultId: 0,
+			cancelId: 1,
+		})
+		.then((result) => ["number", "skip", "overwrite"][result.response]);
+};
+
+/**
+ * Run the download service and forward its events to every window.
+ * Progress is sent at most every 200ms per job.
+ */
+const startService = () => {
+	service = new DownloadService({
+		getOptions: () => options,
+		queueFile,
+		history,
+		askCollision,
+		infoCache,
+	});
+	infoCache.prune();
+	const send = (channel, payload) => {
+		for (const window of BrowserWindow.getAllWindows()) {
+			window.webContents.send(channel, payload);
+		}
+	};
+	const pending = new Map(); // job id => latest progress not sent yet
+	service.on("progress", (job) => {
+		if (pending.has(job.id)) {
+			pending.set(job.id, job);
+			return;
+		}
+		send(EVENTS.progress, job);
+		pending.set(job.id, undefined);
+		setTimeout(() => {
+			const latest = pending.get(job.id);
+			pending.delete(job.id);
+			if (latest) send(EVENTS.progress, latest);
+		}, 200);
+	});
+	// progress held back is stale once the queue itself is sent
+	service.on("queue", () => {
+		for (const id of pending.keys()) pending.set(id, undefined);
+	});
+	for (const event of ["queue", "record", "history"]) {
+		service.on(event, (payload) => send(EVENTS[event], payload));
+	}
+	for (const method of SERVICE_REQUESTS) {
+		ipcMain.handle(REQUESTS[method], (e, ...args) => service[method](...args));
+	}
+	service.start();
+	rpc = new RpcServer(service);
+	configureRpc();
+
+	subscriptions = new Subscriptions({
+		file: path.join(configLocation, "subscriptions.json"),
+		service,
+		getOptions: () => options,
+	});
+	subscriptions.on("change", (list) => send(EVENTS.subscriptions, list));
+	subscriptions.schedule();
+};
+
+const encrypt = (plain) => safeStorage.encryptString(plain);
+const decrypt = (data) => safeStorage.decryptString(data);
+
+/**
+ * Use the proxy settings, with the password stored apart from them
+ */
+const configureProxy = () => proxies.configure({ ...options.proxy, password: proxyPassword });
+
+/**
+ * Whether the proxy has a password, and whether it is saved
+ */
+const proxyStatus = () => ({
+	password: !!proxyPassword,
+	encrypted: safeStorage.isEncryptionAvailable(),
+});
+
+/**
+ * Use a proxy password, saved encrypted when the system has a keyring and
+ * only until the app quits otherwise
+ * @param {String} password 	empty to remove it
+ */
+const storeProxyPassword = (password) => {
+	proxyPassword = `${password || ""}`;
+	if (!proxyPassword) removeSecret(proxyPasswordFile);
+	else if (safeStorage.isEncryptionAvailable()) {
+		saveSecret(proxyPasswordFile, proxyPassword, encrypt);
 	}
+	configureProxy();
+	return proxyStatus();
+};
 
-	if (!options.saveDir) options.saveDir = app.getPath("desktop");
+/**
+ * Start, restart or stop the local API after its settings changed
+ */
+const configureRpc = () => {
+	rpc.configure(options.rpc).catch((err) => {
+		console.error(err);
+		const where = `127.0.0.1:${options.rpc.port}`;
+		dialog.showErrorBox("Local API", `Could not listen on ${where}: ${err.message}`);
+	});
+};
 
+const createWindow = () => {
 	// Create the browser window.
 	const mainWindow = new BrowserWindow({
 		width: options.width,
 		height: options.height,
 		x: options.x,
 		y: options.y,
-		webPreferences: { nodeIntegration: true, contextIsolation: false },
+		webPreferences: {
+			preload: path.join(__dirname, "preload.js"),
+			nodeIntegration: false,
+			contextIsolation: true,
+			sandbox: true,
+		},
 	});
 
+	// the window only shows the app, links and injected content go nowhere
+	mainWindow.webContents.on("will-navigate", (e) => e.preventDefault());
+	mainWindow.webContents.setWindowOpenHandler(() => ({ action: "deny" }));
+
 	// and load the index.html of the app.
 	mainWindow.loadFile(path.join(__dirname, "index.html"));
 
@@ -47,10 +202,59 @@ const createWindow = () => {
 	});
 };
 
+/**
+ * Show a link in the window, or keep it until the window asks for it
+ * @param {Object} link 	from parseLink() in links.js
+ */
+const openLink = (link) => {
+	if (!link) return;
+	const window = BrowserWindow.getAllWindows()[0];
+	if (window) {
+		if (window.isMinimized()) window.restore();
+		window.focus();
+	}
+	if (linkTarget && !linkTarget.isDestroyed()) {
+		linkTarget.send(EVENTS.link, link);
+	} else {
+		pendingLink = link;
+	}
+};
+
+if (process.defaultApp) {
+	// started with `electron .`, which needs the app path to launch again
+	app.setAsDefaultProtocolClient(PROTOCOL, process.execPath, [path.resolve(process.argv[1])]);
+} else {
+	app.setAsDefaultProtocolClient(PROTOCOL);
+}
+
+app.on("second-instance", (e, argv) => {
+	if (!BrowserWindow.getAllWindows().length && app.isReady()) createWindow();
+	openLink(linkFromArgs(argv));
+});
+
+// macOS opens links through this event rather than arguments
+app.on("open-url", (e, url) => {
+	e.preventDefault();
+	openLink(parseLink(url));
+});
+
 // This method will be called when Electron has finished
 // initialization and is ready to create browser windows.
 // Some APIs can only be used after this event occurs.
-app.on("ready", createWindow);
+app.on("ready", () => {
+	options = loadOptions(configFile, { saveDir: app.getPath("desktop"), configLocation });
+	if (safeStorage.isEncryptionAvailable()) {
+		cookies.set(loadSecret(cookieFile, decrypt));
+		proxyPassword = loadSecret(proxyPasswordFile, decrypt);
+	}
+	// earlier versions saved the password in config.json
+	if (options.proxy.password) storeProxyPassword(options.proxy.password);
+	options.proxy.password = "";
+	configureProxy();
+	startService();
+	createWindow();
+	openLink(linkFromArgs(process.argv));
+});
 
 // Quit when all windows are closed, except on macOS. There, it's common
 // for applications and their menu bar to stay active until the user quits
@@ -69,23 +273,221 @@ app.on("activate", () => {
 	}
 });
 
-ipcMain.on("load-save-dir", (e) => {
-	e.reply("save-dir", options.saveDir);
+ipcMain.on("ytdl-api", (e) => {
+	e.returnValue = { REQUESTS, EVENTS };
 });
 
-ipcMain.on("set-save-dir", (e) => {
-	dialog
-		.showOpenDialog({
-			title: "Where do you want to save your downloads?",
-			defaultPath: options.saveDir,
-			properties: ["openDirectory"],
-		})
-		.then((result) => {
-			if (result.canceled) return;
-			e.reply("save-dir", result.filePaths[0]);
-			options.saveDir = result.filePaths[0];
-		})
-		.catch((err) => {
-			console.error(err);
+ipcMain.handle(REQUESTS.takeLink, (e) => {
+	linkTarget = e.sender;
+	const link = pendingLink;
+	pendingLink = undefined;
+	return link;
+});
+
+ipcMain.handle(REQUESTS.listSubscriptions, () => subscriptions.get());
+/**
+ * Subscription settings sent by a window, whose save locations are only
+ * picked in the dialog of chooseSubscriptionDir
+ */
+const windowSubscription = (settings) => {
+	if (settings && settings.saveDir) throw new Error("saveDir cannot be set from a window");
+	return settings;
+};
+
+ipcMain.handle(REQUESTS.addSubscription, (e, channel, settings) =>
+	subscriptions.add(channel, windowSubscription(settings))
+);
+ipcMain.handle(REQUESTS.updateSubscription, (e, channelId, changes) =>
+	subscriptions.update(channelId, windowSubscription(changes))
+);
+ipcMain.handle(REQUESTS.chooseSubscriptionDir, async (e, channelId) => {
+	const sub = subscriptions.get().find((s) => s.channelId === channelId);
+	if (!sub) return;
+	const result = await dialog.showOpenDialog({
+		title: `Where do you want to save the uploads of ${sub.title}?`,
+		defaultPath: sub.saveDir || options.saveDir,
+		properties: ["openDirectory"],
+	});
+	if (result.canceled) return;
+	subscriptions.update(channelId, { saveDir: result.filePaths[0] });
+});
+ipcMain.handle(REQUESTS.removeSubscription, (e, channelId) => subscriptions.remove(channelId));
+ipcMain.handle(REQUESTS.checkSubscriptions, (e, channelId) => subscriptions.check(channelId));
+
+ipcMain.handle(REQUESTS.loadSaveDir, () => options.saveDir);
+
+ipcMain.handle(REQUESTS.setSaveDir, async () => {
+	const result = await dialog.showOpenDialog({
+		title: "Where do you want to save your downloads?",
+		defaultPath: options.saveDir,
+		properties: ["openDirectory"],
+	});
+	if (result.canceled) return undefined;
+	options.saveDir = result.filePaths[0];
+	return options.saveDir;
+});
+
+/**
+ * The options as windows see them, without the token of the local API. The
+ * proxy password is not in the options at all, see storeProxyPassword().
+ */
+const windowOptions = () => ({ ...options, rpc: { ...options.rpc, token: "" } });
+
+ipcMain.handle(REQUESTS.loadOptions, windowOptions);
+
+ipcMain.handle(REQUESTS.setOption, (e, key, value) => {
+	options[key] = checkOption(key, value, options);
+	if (key === "maxConcurrent") service.setMaxConcurrent(options.maxConcurrent);
+	if (key === "rpc") configureRpc();
+	if (key === "subscriptions") subscriptions.schedule();
+	if (key === "proxy") configureProxy();
+});
+
+/**
+ * Send the options to every window after the main process changed them
+ */
+const sendOptions = () => {
+	for (const window of BrowserWindow.getAllWindows()) {
+		window.webContents.send(EVENTS.options, windowOptions());
+	}
+};
+
+ipcMain.handle(REQUESTS.importBatch, async () => {
+	const result = await dialog.showOpenDialog({
+		title: "Import URLs",
+		properties: ["openFile"],
+		filters: [
+			{ name: "URL lists", extensions: ["txt", "csv", "tsv"] },
+			{ name: "All files", extensions: ["*"] },
+		],
+	});
+	if (result.canceled) return undefined;
+	const file = result.filePaths[0];
+	if (fs.statSync(file).size > MAX_BATCH_FILE) throw new Error("the file is too large");
+	return fs.readFileSync(file, "utf8");
+});
+
+/**
+ * How many cookies are sent to YouTube, and whether they are saved
+ */
+const cookieStatus = () => ({
+	count: cookies.count(),
+	saved: fs.existsSync(cookieFile),
+	encrypted: safeStorage.isEncryptionAvailable(),
+});
+
+/**
+ * Use the cookies of a cookies.txt file or a Cookie header, saved encrypted
+ * when the system has a keyring and only until the app quits otherwise
+ * @param {String} text
+ */
+const storeCookies = (text) => {
+	const { header, error } = cookieHeader(`${text || ""}`);
+	if (error) return { ...cookieStatus(), error };
+	cookies.set(header);
+	if (safeStorage.isEncryptionAvailable()) {
+		saveSecret(cookieFile, header, encrypt);
+	}
+	return cookieStatus();
+};
+
+ipcMain.handle(REQUESTS.cookieStatus, cookieStatus);
+
+ipcMain.handle(REQUESTS.setCookies, (e, text) => storeCookies(text));
+
+ipcMain.handle(REQUESTS.importCookies, async () => {
+	const result = await dialog.showOpenDialog({
+		title: "Import cookies.txt",
+		properties: ["openFile"],
+		filters: [
+			{ name: "Cookies", extensions: ["txt"] },
+			{ name: "All files", extensions: ["*"] },
+		],
+	});
+	if (result.canceled) return cookieStatus();
+	return storeCookies(fs.readFileSync(result.filePaths[0], "utf8"));
+});
+
+ipcMain.handle(REQUESTS.clearCookies, () => {
+	cookies.set("");
+	removeSecret(cookieFile);
+	return cookieStatus();
+});
+
+ipcMain.handle(REQUESTS.proxyStatus, proxyStatus);
+
+ipcMain.handle(REQUESTS.setProxyPassword, (e, password) => storeProxyPassword(password));
+
+ipcMain.handle(REQUESTS.chooseFfmpeg, async () => {
+	const result = await dialog.showOpenDialog({
+		title: "Where is ffmpeg?",
+		defaultPath: options.ffmpegPath || undefined,
+		properties: ["openFile"],
+	});
+	if (result.canceled) return;
+	options.ffmpegPath = result.filePaths[0];
+	sendOptions();
+});
+
+ipcMain.handle(REQUESTS.chooseArchive, async () => {
+	const result = await dialog.showSaveDialog({
+		title: "Download archive file",
+		defaultPath: options.archive.file,
+		filters: [{ name: "Archive", extensions: ["txt"] }],
+	});
+	if (result.canceled) return;
+	options.archive.file = result.filePath;
+	sendOptions();
+});
+
+ipcMain.handle(REQUESTS.importArchive, async () => {
+	try {
+		const result = await dialog.showOpenDialog({
+			title: "Import a download archive",
+			properties: ["openFile"],
 		});
+		if (result.canceled) return;
+		const added = new Archive(options.archive.file).importFrom(result.filePaths[0]);
+		dialog.showMessageBox({ message: `Imported ${added} new entries` });
+	} catch (err) {
+		console.error(err);
+		dialog.showErrorBox("Import failed", `${err}`);
+	}
+});
+
+ipcMain.handle(REQUESTS.exportArchive, async () => {
+	try {
+		const result = await dialog.showSaveDialog({
+			title: "Export the download archive",
+			defaultPath: "archive.txt",
+		});
+		if (result.canceled) return;
+		new Archive(options.archive.file).exportTo(result.filePath);
+	} catch (err) {
+		console.error(err);
+		dialog.showErrorBox("Export failed", `${err}`);
+	}
+});
+
+ipcMain.handle(REQUESTS.copyRpcToken, () => clipboard.writeText(options.rpc.token));
+
+ipcMain.handle(REQUESTS.newRpcToken, () => {
+	options.rpc = { ...options.rpc, token: newToken() };
+	configureRpc();
+	sendOptions();
+});
+
+/**
+ * Whether a window may open a path: the save location or a download
+ * @param {String} p
+ */
+const isKnownPath = (p) =>
+	p === options.saveDir ||
+	service.getQueue().some((j) => j.filename === p) ||
+	service.getHistory().some((e) => e.path === p);
+
+ipcMain.handle(REQUESTS.openPath, (e, p) => (isKnownPath(p) ? shell.openPath(p) : ""));
+
+ipcMain.handle(REQUESTS.showItemInFolder, (e, p) => {
+	if (isKnownPath(p)) shell.showItemInFolder(p);
 });
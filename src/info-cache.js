@@ -0,0 +1,189 @@
+const fs = require("fs");
+const path = require("path");
+const ytdl = require("ytdl-core");
+const { proxies } = require("./proxy.js");
+const { cookies } = require("./cookies.js");
+
+/**
+ * Cache settings, stored as options.infoCache. Downloads reuse cached info
+ * for `formatMinutes` since the format and caption URLs in it expire after
+ * a few hours. Showing a video reuses it for `metadataDays`, the formats
+ * listed stay the same even when their URLs have expired.
+ */
+const DEFAULT_INFO_CACHE = { enabled: true, formatMinutes: 30, metadataDays: 7 };
+
+/**
+ * Parts of ytdl.getInfo() kept in the cache, see summarizeInfo() in
+ * service.js for what is read from them
+ */
+function trimInfo(info) {
+	return {
+		videoDetails: info.videoDetails,
+		formats: info.formats,
+		player_response: { captions: info.player_response && info.player_response.captions },
+		response: { playerOverlays: info.response && info.response.playerOverlays },
+		html5player: info.html5player,
+		// lets ytdl.downloadFromInfo() accept the cached info
+		full: info.full,
+	};
+}
+
+/**
+ * ytdl.getInfo() through the proxy, if one is set, with the imported cookies
+ * @param {String} url
+ */
+function fetchInfo(url) {
+	return proxies.attempt(url, (options) =>
+		ytdl.getInfo(url, { requestOptions: cookies.requestOptions(options) })
+	);
+}
+
+/**
+ * Id of the video a URL points to, if any
+ * @param {String} url
+ */
+function videoIdOf(url) {
+	try {
+		return ytdl.getURLVideoID(url);
+	} catch (err) {
+		return undefined;
+	}
+}
+
+function remove(file) {
+	try {
+		fs.unlinkSync(file);
+	} catch (err) {}
+}
+
+/**
+ * The info of videos by id, one JSON file each in a folder, so opening a
+ * video again or queuing it after Get Info skips ytdl.getInfo()
+ */
+class InfoCache {
+	/**
+	 * @param {String} dir 	where the entries are saved
+	 * @param {Function} getSettings 	returns the current options.infoCache
+	 */
+	constructor(dir, getSettings) {
+		this.dir = dir;
+		this.getSettings = () => ({ ...DEFAULT_INFO_CACHE, ...getSettings() });
+		this.hits = 0;
+		this.misses = 0;
+	}
+
+	fileOf(videoId) {
+		return path.join(this.dir, `${videoId}.json`);
+	}
+
+	/**
+	 * The info of a video, fetched if the cached one is older than maxAge
+	 * @param {String} url
+	 * @param {Number} maxAge 	in ms, 0 to always fetch
+	 * @returns {Promise<Object>} result of ytdl.getInfo(), trimmed when cached
+	 */
+	async getInfo(url, maxAge) {
+		const videoId = videoIdOf(url);
+		if (!this.getSettings().enabled || !videoId) return fetchInfo(url);
+		const cached = maxAge > 0 ? this.read(videoId) : undefined;
+		if (cached && Date.now() - cached.fetched < maxAge) {
+			this.hits++;
+			return cached.info;
+		}
+		this.misses++;
+		const info = await fetchInfo(url);
+		this.write(info.videoDetails.videoId || videoId, info);
+		return info;
+	}
+
+	/**
+	 * Info to show a video with
+	 */
+	getMetadata(url) {
+		return this.getInfo(url, this.getSettings().metadataDays * 86400000);
+	}
+
+	/**
+	 * Info to download a video with, whose format URLs still work
+	 */
+	getFormats(url) {
+		return this.getInfo(url, this.getSettings().formatMinutes * 60000);
+	}
+
+	read(videoId) {
+		try {
+			return JSON.parse(fs.readFileSync(this.fileOf(videoId), "utf8"));
+		} catch (err) {
+			return undefined;
+		}
+	}
+
+	write(videoId, info) {
+		try {
+			fs.mkdirSync(this.dir, { recursive: true });
+			const entry = { fetched: Date.now(), info: trimInfo(info) };
+			// write then rename, the CLI may read the same entry
+			const tmp = `${this.fileOf(videoId)}.${process.pid}.tmp`;
+			fs.writeFileSync(tmp, JSON.stringify(entry));
+			fs.renameSync(tmp, this.fileOf(videoId));
+		} catch (err) {
+			console.error(err);
+		}
+	}
+
+	/**
+	 * Cached entries with their file size and age
+	 * @returns {Array} { file, size, fetched }
+	 */
+	entries() {
+		let names = [];
+		try {
+			names = fs.readdirSync(this.dir).filter((n) => n.endsWith(".json"));
+		} catch (err) {
+			return [];
+		}
+		const entries = [];
+		for (const name of names) {
+			const file = path.join(this.dir, name);
+			try {
+				const stat = fs.statSync(file);
+				entries.push({ file, size: stat.size, fetched: stat.mtimeMs });
+			} catch (err) {}
+		}
+		return entries;
+	}
+
+	/**
+	 * Delete the entries too old to be shown
+	 * @returns {Number} how many were deleted
+	 */
+	prune() {
+		const maxAge = this.getSettings().metadataDays * 86400000;
+		const old = this.entries().filter((e) => Date.now() - e.fetched >= maxAge);
+		for (const { file } of old) remove(file);
+		return old.length;
+	}
+
+	clear() {
+		for (const { file } of this.entries()) remove(file);
+		this.hits = this.misses = 0;
+	}
+
+	/**
+	 * @returns {Object} { entries, size, oldest, hits, misses } where size is
+	 * 					in bytes, oldest is when the oldest entry was fetched
+	 * 					and hits and misses count since the app started
+	 */
+	stats() {
+		const entries = this.entries();
+		return {
+			entries: entries.length,
+			size: entries.reduce((sum, e) => sum + e.size, 0),
+			oldest: entries.length ? Math.min(...entries.map((e) => e.fetched)) : 0,
+			hits: this.hits,
+			misses: this.misses,
+		};
+	}
+}
+
+module.exports = { DEFAULT_INFO_CACHE, fetchInfo, InfoCache };
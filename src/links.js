@@ -0,0 +1,57 @@
+/**
+ * Scheme of the links the app opens, e.g. from a browser bookmarklet
+ */
+const PROTOCOL = "ytdl-desktop";
+
+/**
+ * What to do with links opened from outside the app, stored as
+ * options.links. `preset` is a key of PREFERENCES or the name of a preset to
+ * queue the video or playlist with, which gets its info first regardless of
+ * `getInfo`. Queuing from a link is confirmed first unless `autoQueue` is set.
+ */
+const DEFAULT_LINKS = { getInfo: true, preset: "", autoQueue: false };
+
+/**
+ * Parse a link the app was launched with. Accepts
+ * - ytdl-desktop://open?url=<encoded url>&preset=<preference or preset name>&info=<0|1>
+ * - ytdl-desktop://www.youtube.com/watch?v=..., opened as https
+ * - plain http(s) URLs
+ * @param {String} arg
+ * @returns {Object} { url, preset, getInfo } where preset and getInfo are
+ * 					undefined unless the link sets them, or undefined if the
+ * 					argument is not a link. The window looks preset up among
+ * 					PREFERENCES and the presets of the options.
+ */
+function parseLink(arg) {
+	if (/^https?:\/\//i.test(arg)) return { url: arg };
+	const prefix = `${PROTOCOL}://`;
+	if (!`${arg}`.toLowerCase().startsWith(prefix)) return undefined;
+	const rest = arg.slice(prefix.length);
+	if (!/^open\/?\?/i.test(rest)) return { url: `https://${rest}` };
+
+	const params = new URLSearchParams(rest.slice(rest.indexOf("?") + 1));
+	const url = params.get("url");
+	if (!url || !/^https?:\/\//i.test(url)) return undefined;
+	const link = { url };
+	const preset = params.get("preset");
+	if (preset !== null) link.preset = preset;
+	const info = params.get("info");
+	if (info !== null) link.getInfo = info !== "0" && info !== "false";
+	return link;
+}
+
+/**
+ * The last link among command line arguments, skipping the executable and
+ * Chromium switches
+ * @param {Array} argv
+ */
+function linkFromArgs(argv) {
+	for (let i = argv.length - 1; i > 0; i--) {
+		if (argv[i].startsWith("-")) continue;
+		const link = parseLink(argv[i]);
+		if (link) return link;
+	}
+	return undefined;
+}
+
+module.exports = { PROTOCOL, DEFAULT_LINKS, parseLink, linkFromArgs };
@@ -0,0 +1,53 @@
+const { fetchToFile } = require("./http.js");
+
+/**
+ * Default embedding settings, stored as options.embed
+ */
+const DEFAULT_EMBED = { metadata: true, thumbnail: true };
+
+/**
+ * Tags written into downloaded files, as ffmpeg metadata keys. ffmpeg maps
+ * them to ID3v2 frames, MP4 atoms or Matroska tags depending on the muxer.
+ * @param {Object} info 	result of ytdl.getInfo()
+ */
+function tagsOf(info) {
+	const d = info.videoDetails;
+	const tags = {
+		title: d.title,
+		artist: d.author ? d.author.name : d.ownerChannelName,
+		date: d.publishDate || d.uploadDate,
+		description: d.description,
+		comment: d.video_url,
+	};
+	for (const key of Object.keys(tags)) {
+		if (!tags[key]) delete tags[key];
+	}
+	return tags;
+}
+
+/**
+ * The highest resolution thumbnail of a video
+ * @param {Object} info 	result of ytdl.getInfo()
+ */
+function bestThumbnail(info) {
+	const thumbnails = [...(info.videoDetails.thumbnails || [])];
+	thumbnails.sort((a, b) => b.width * b.height - a.width * a.height);
+	return thumbnails[0];
+}
+
+/**
+ * Save the best thumbnail of a video next to a download. Resolves with
+ * { file, mimetype }, or undefined if the video has no thumbnail.
+ * @param {Object} info 	result of ytdl.getInfo()
+ * @param {String} filename 	the download the thumbnail belongs to
+ */
+async function fetchThumbnail(info, filename) {
+	const thumbnail = bestThumbnail(info);
+	if (!thumbnail) return undefined;
+	const webp = /\.webp(\?|$)/.test(thumbnail.url);
+	const file = `${filename}.thumb.${webp ? "webp" : "jpg"}`;
+	await fetchToFile(thumbnail.url, file);
+	return { file, mimetype: webp ? "image/webp" : "image/jpeg" };
+}
+
+module.exports = { DEFAULT_EMBED, tagsOf, bestThumbnail, fetchThumbnail };
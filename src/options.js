@@ -0,0 +1,262 @@
+const fs = require("fs");
+const os = require("os");
+const path = require("path");
+const { DEFAULT_RETRY, ERROR_CLASSES } = require("./retry");
+const { CONTAINERS, AUDIO_CODECS, DEFAULT_CONVERT } = require("./ffmpeg");
+const { DEFAULT_EMBED } = require("./metadata");
+const { DEFAULT_SUBTITLES, SUBTITLE_FORMATS } = require("./subtitles");
+const { DEFAULT_CHAPTERS, CHAPTER_MODES } = require("./chapters");
+const { DEFAULT_CLIP } = require("./clip");
+const {
+	DEFAULT_TEMPLATE,
+	DEFAULT_PLAYLIST_TEMPLATE,
+	DEFAULT_CHAPTER_TEMPLATE,
+	validateTemplate,
+} = require("./template");
+const { PROFILES, COLLISIONS, defaultProfile } = require("./sanitize");
+const { DEFAULT_ARCHIVE } = require("./archive");
+const { DEFAULT_LINKS } = require("./links");
+const { DEFAULT_RPC, newToken } = require("./rpc");
+const {
+	DEFAULT_PRESETS,
+	DEFAULT_PRESET_SETTINGS,
+	parseRule,
+	findPreset,
+} = require("./presets");
+const { DEFAULT_SUBSCRIPTIONS } = require("./subscriptions");
+const { DEFAULT_BATCH } = require("./batch");
+const { DEFAULT_INFO_CACHE } = require("./info-cache");
+const { DEFAULT_PROXY, checkProxy } = require("./proxy");
+const { PREFERENCES } = require("./playlist");
+const { productName } = require("../package.json");
+
+/**
+ * Folder holding config.json, the queue and the history. The same as
+ * app.getPath("userData") in Electron, for use outside of it.
+ */
+function configLocation() {
+	const home = os.homedir();
+	switch (process.platform) {
+		case "win32":
+			return path.join(process.env.APPDATA || path.join(home, "AppData", "Roaming"), productName);
+		case "darwin":
+			return path.join(home, "Library", "Application Support", productName);
+		default:
+			return path.join(process.env.XDG_CONFIG_HOME || path.join(home, ".config"), productName);
+	}
+}
+
+/**
+ * Read the app options from config.json, filling in the defaults of
+ * anything missing
+ * @param {String} configFile
+ * @param {Object} defaults 	{ saveDir, configLocation } where the archive
+ * 							file defaults to archive.txt in configLocation
+ */
+function loadOptions(configFile, defaults) {
+	let options;
+	try {
+		options = JSON.parse(fs.readFileSync(configFile, "utf8"));
+	} catch (e) {
+		options = { width: 880, height: 845 };
+	}
+
+	if (!options.saveDir) options.saveDir = defaults.saveDir;
+	if (!options.maxConcurrent) options.maxConcurrent = 2;
+	options.retry = { ...DEFAULT_RETRY, ...options.retry };
+	options.convert = { ...DEFAULT_CONVERT, ...options.convert };
+	options.embed = { ...DEFAULT_EMBED, ...options.embed };
+	options.subtitles = { ...DEFAULT_SUBTITLES, ...options.subtitles };
+	options.chapters = { ...DEFAULT_CHAPTERS, ...options.chapters };
+	options.clip = { ...DEFAULT_CLIP, ...options.clip };
+	if (!options.template) options.template = DEFAULT_TEMPLATE;
+	if (!options.playlistTemplate) options.playlistTemplate = DEFAULT_PLAYLIST_TEMPLATE;
+	if (!options.chapterTemplate) options.chapterTemplate = DEFAULT_CHAPTER_TEMPLATE;
+	if (!options.sanitize) options.sanitize = defaultProfile();
+	if (!options.collision) options.collision = "number";
+	options.links = { ...DEFAULT_LINKS, ...options.links };
+	if (!Array.isArray(options.presets)) options.presets = DEFAULT_PRESETS;
+	options.defaultPreset = { ...DEFAULT_PRESET_SETTINGS, ...options.defaultPreset };
+	options.subscriptions = { ...DEFAULT_SUBSCRIPTIONS, ...options.subscriptions };
+	options.batch = { ...DEFAULT_BATCH, ...options.batch };
+	options.infoCache = { ...DEFAULT_INFO_CACHE, ...options.infoCache };
+	options.proxy = { ...DEFAULT_PROXY, ...options.proxy };
+	options.rpc = { ...DEFAULT_RPC, ...options.rpc };
+	if (!options.rpc.token) options.rpc.token = newToken();
+	options.archive = { ...DEFAULT_ARCHIVE, ...options.archive };
+	if (!options.archive.file) {
+		options.archive.file = path.join(defaults.configLocation, "archive.txt");
+	}
+	return options;
+}
+
+const isObject = (value) => typeof value === "object" && value !== null && !Array.isArray(value);
+const has = (table, key) => Object.prototype.hasOwnProperty.call(table, key);
+const typeOf = (value) => (Array.isArray(value) ? "array" : typeof value);
+
+/**
+ * The fields of a settings object sent by a window over the current ones,
+ * leaving out fields its defaults do not have
+ * @param {String} key 	option key, for errors
+ * @param {Object} defaults 	the DEFAULT_* of the option
+ * @param {Object} current 	the option now
+ * @param {Object} value 	sent by the window
+ * @throws if a field has another type than in the defaults
+ */
+function fieldsOf(key, defaults, current, value) {
+	if (!isObject(value)) throw new Error(`${key} must be an object`);
+	const merged = { ...defaults, ...current };
+	for (const [field, fallback] of Object.entries(defaults)) {
+		if (value[field] === undefined) continue;
+		if (typeOf(value[field]) !== typeOf(fallback)) {
+			throw new Error(`${key}.${field} must be a ${typeOf(fallback)}`);
+		}
+		merged[field] = value[field];
+	}
+	return merged;
+}
+
+function between(key, value, min, max, whole = false) {
+	if (typeof value !== "number" || !(value >= min && value <= max)) {
+		throw new Error(`${key} must be between ${min} and ${max}`);
+	}
+	if (whole && !Number.isInteger(value)) throw new Error(`${key} must be a whole number`);
+	return value;
+}
+
+function oneOf(key, value, table) {
+	if (typeof value !== "string" || !has(table, value)) throw new Error(`unknown ${key} ${value}`);
+	return value;
+}
+
+function template(value) {
+	const error = typeof value === "string" ? validateTemplate(value) : "template must be text";
+	if (error) throw new Error(error);
+	return value;
+}
+
+function presets(value) {
+	if (!Array.isArray(value)) throw new Error("presets must be a list");
+	const names = new Set();
+	return value.map((preset) => {
+		const fields = { name: "", rule: "", container: "" };
+		const { name, rule, container } = fieldsOf("preset", fields, {}, preset);
+		if (!name || names.has(name)) throw new Error("preset names must be set and unique");
+		names.add(name);
+		const { error } = parseRule(rule);
+		if (error) throw new Error(`${name}: ${error}`);
+		if (container) oneOf("container", container, CONTAINERS);
+		return { name, rule, container };
+	});
+}
+
+/**
+ * Options windows may change, by key, each checking the value sent with the
+ * current value and all the options, and returning what to store. Paths are
+ * only picked in the dialogs of index.js, the token of the local API and the
+ * proxy password are kept.
+ */
+const WINDOW_OPTIONS = {
+	maxConcurrent: (value) => between("maxConcurrent", value, 1, 16, true),
+	mergeContainer: (value) => oneOf("container", value, CONTAINERS),
+	template,
+	playlistTemplate: template,
+	chapterTemplate: template,
+	sanitize: (value) => oneOf("profile", value, PROFILES),
+	collision: (value) => oneOf("collision policy", value, COLLISIONS),
+	// cleared to search PATH again, set with the Browse dialog
+	ffmpegPath: (value) => {
+		if (value !== "") throw new Error("ffmpeg is picked with Browse");
+		return value;
+	},
+	convert: (value, current) => {
+		const convert = fieldsOf("convert", DEFAULT_CONVERT, current, value);
+		if (convert.codec !== "original") oneOf("audio format", convert.codec, AUDIO_CODECS);
+		between("convert.mp3Quality", convert.mp3Quality, 0, 9, true);
+		between("convert.bitrate", convert.bitrate, 32, 512, true);
+		return convert;
+	},
+	embed: (value, current) => fieldsOf("embed", DEFAULT_EMBED, current, value),
+	subtitles: (value, current) => {
+		const subtitles = fieldsOf("subtitles", DEFAULT_SUBTITLES, current, value);
+		oneOf("subtitle format", subtitles.format, SUBTITLE_FORMATS);
+		return subtitles;
+	},
+	chapters: (value, current) => {
+		const chapters = fieldsOf("chapters", DEFAULT_CHAPTERS, current, value);
+		oneOf("chapter mode", chapters.mode, CHAPTER_MODES);
+		return chapters;
+	},
+	clip: (value, current) => fieldsOf("clip", DEFAULT_CLIP, current, value),
+	presets,
+	defaultPreset: (value, current) =>
+		fieldsOf("defaultPreset", DEFAULT_PRESET_SETTINGS, current, value),
+	links: (value, current, options) => {
+		const links = fieldsOf("links", DEFAULT_LINKS, current, value);
+		// a preference or the name of a preset
+		const { preset } = links;
+		if (preset && !has(PREFERENCES, preset) && !findPreset(options.presets, preset)) {
+			throw new Error(`unknown preset ${preset}`);
+		}
+		return links;
+	},
+	subscriptions: (value, current) => {
+		const subscriptions = fieldsOf("subscriptions", DEFAULT_SUBSCRIPTIONS, current, value);
+		between("subscriptions.interval", subscriptions.interval, 5, 10080, true);
+		return subscriptions;
+	},
+	batch: (value, current) => {
+		const batch = fieldsOf("batch", DEFAULT_BATCH, current, value);
+		between("batch.concurrency", batch.concurrency, 1, 16, true);
+		return batch;
+	},
+	infoCache: (value, current) => {
+		const cache = fieldsOf("infoCache", DEFAULT_INFO_CACHE, current, value);
+		between("infoCache.formatMinutes", cache.formatMinutes, 0, 300);
+		between("infoCache.metadataDays", cache.metadataDays, 0, 3650);
+		return cache;
+	},
+	retry: (value, current) => {
+		const retry = fieldsOf("retry", DEFAULT_RETRY, current, value);
+		for (const key of ["maxAttempts", "baseDelay", "maxDelay", "jitter"]) {
+			between(`retry.${key}`, retry[key], 0, 86400);
+		}
+		const retryOn = {};
+		for (const key of Object.keys(ERROR_CLASSES)) retryOn[key] = retry.retryOn[key] === true;
+		return { ...retry, retryOn };
+	},
+	archive: (value, current) => ({
+		...fieldsOf("archive", DEFAULT_ARCHIVE, current, value),
+		file: current.file,
+	}),
+	rpc: (value, current) => {
+		const rpc = fieldsOf("rpc", DEFAULT_RPC, current, value);
+		between("rpc.port", rpc.port, 1024, 65535, true);
+		return { ...rpc, token: current.token };
+	},
+	proxy: (value, current) => {
+		const proxy = fieldsOf("proxy", DEFAULT_PROXY, current, value);
+		for (const url of [proxy.url, ...proxy.pool].filter((u) => u)) {
+			const error = typeof url === "string" ? checkProxy(url) : "proxies must be text";
+			if (error) throw new Error(error);
+		}
+		return { ...proxy, password: current.password };
+	},
+};
+
+/**
+ * Check an option a window changes
+ * @param {String} key
+ * @param {*} value 	sent by the window
+ * @param {Object} options 	the app options now
+ * @returns {*} the value to store
+ * @throws if windows cannot change the option or the value is invalid
+ */
+function checkOption(key, value, options) {
+	if (typeof key !== "string" || !has(WINDOW_OPTIONS, key)) {
+		throw new Error(`${key} cannot be changed from a window`);
+	}
+	return WINDOW_OPTIONS[key](value, options[key] || {}, options);
+}
+
+module.exports = { configLocation, loadOptions, checkOption };
@@ -0,0 +1,116 @@
+import { html, render } from "../node_modules/lit-html/lit-html.js";
+import { constants } from "./constants.js";
+
+let selected = new Set(); // indexes of the entries to download
+let preference = "merged";
+let numbered = true;
+
+/**
+ * Render a resolved playlist with a checkbox per entry
+ * @param {Element} container
+ * @param {Object} playlist 	result of getPlaylist()
+ * @param {Function} enqueue 	called with (items, preference, numbered)
+ * 								where numbered selects the playlist template
+ * @param {Set} archived 	ids of the videos in the download archive
+ */
+export function renderPlaylist(container, playlist, enqueue, archived) {
+	const update = () => renderPlaylist(container, playlist, enqueue, archived);
+	const playable = playlist.items.filter((i) => i.playable);
+	const toggle = (item, checked) => {
+		if (checked) selected.add(item.index);
+		else selected.delete(item.index);
+		update();
+	};
+
+	render(
+		html`<h3>${playlist.title}</h3>
+			<div class="merge-row">
+				<button
+					@click=${() => {
+						selected = new Set(playable.map((i) => i.index));
+						update();
+					}}
+				>
+					Select all
+				</button>
+				<button
+					@click=${() => {
+						selected.clear();
+						update();
+					}}
+				>
+					Select none
+				</button>
+				<select @change=${(e) => (preference = e.target.value)}>
+					${Object.entries(constants.preferences).map(
+						([key, label]) =>
+							html`<option value=${key} .selected=${key === preference}>${label}</option>`
+					)}
+				</select>
+				<label>
+					<input
+						type="checkbox"
+						.checked=${numbered}
+						@change=${(e) => (numbered = e.target.checked)}
+					/>
+					Use playlist filename template
+				</label>
+				<button
+					?disabled=${!selected.size}
+					@click=${() => {
+						enqueue(
+							playlist.items.filter((i) => selected.has(i.index)),
+							preference,
+							numbered
+						);
+						selected.clear();
+						update();
+					}}
+				>
+					Download ${selected.size} selected
+				</button>
+			</div>
+			<table>
+				<tbody>
+					<tr>
+						<th></th>
+						<th>#</th>
+						<th>Title</th>
+						<th>Duration</th>
+						<th>Available</th>
+						<th>Archived</th>
+					</tr>
+					${playlist.items.map(
+						(i) => html`<tr>
+							<td>
+								<input
+									type="checkbox"
+									?disabled=${!i.playable}
+									.checked=${selected.has(i.index)}
+									@change=${(e) => toggle(i, e.target.checked)}
+								/>
+							</td>
+							<td>${i.index}</td>
+							<td class="title">${i.title}</td>
+							<td>${i.durationText}</td>
+							<td>${i.playable ? "√" : ""}</td>
+							<td>${archived.has(i.id) ? "√" : ""}</td>
+						</tr>`
+					)}
+				</tbody>
+			</table>`,
+		container
+	);
+}
+
+/**
+ * Select every available entry of a newly loaded playlist that is not in the
+ * download archive
+ * @param {Object} playlist 	result of getPlaylist()
+ * @param {Set} archived 	ids of the videos in the download archive
+ */
+export function resetSelection(playlist, archived) {
+	selected = new Set(
+		playlist.items.filter((i) => i.playable && !archived.has(i.id)).map((i) => i.index)
+	);
+}
@@ -0,0 +1,61 @@
+const ytpl = require("ytpl");
+const { proxies } = require("./proxy.js");
+const { cookies } = require("./cookies.js");
+
+/**
+ * Ways to pick formats for every video of a playlist
+ */
+const PREFERENCES = {
+	merged: "Best video + best audio (merged)",
+	single: "Best format with video and audio",
+	audio: "Best audio only",
+};
+
+/**
+ * Whether a URL should be opened as a playlist rather than a single video.
+ * Watch URLs that are part of a playlist open the video.
+ * @param {String} url
+ */
+function isPlaylistUrl(url) {
+	if (/[?&]v=|youtu\.be\//.test(url)) return false;
+	return ytpl.validateID(url);
+}
+
+/**
+ * Playlist id of a watch URL that is part of a playlist, if any
+ * @param {String} url
+ */
+function playlistOf(url) {
+	const m = /[?&]list=([\w-]+)/.exec(url);
+	return m ? m[1] : undefined;
+}
+
+/**
+ * Resolve every entry of a playlist, through the proxy if one is set and
+ * with the imported cookies
+ * @param {String} url 	a playlist URL or id
+ */
+async function getPlaylist(url) {
+	// ids are fetched from youtube.com like the URLs
+	const target = /^https?:\/\//i.test(url) ? url : `https://www.youtube.com/playlist?list=${url}`;
+	const playlist = await proxies.attempt(target, (options) =>
+		ytpl(url, { limit: Infinity, requestOptions: cookies.requestOptions(options) })
+	);
+	return {
+		id: playlist.id,
+		title: playlist.title,
+		author: playlist.author ? playlist.author.name : "",
+		url: playlist.url,
+		items: playlist.items.map((item) => ({
+			index: item.index,
+			id: item.id,
+			url: item.shortUrl || item.url,
+			title: item.title,
+			duration: item.durationSec,
+			durationText: item.duration || "",
+			playable: item.isPlayable,
+		})),
+	};
+}
+
+module.exports = { PREFERENCES, isPlaylistUrl, playlistOf, getPlaylist };
@@ -0,0 +1,21 @@
+const { contextBridge, ipcRenderer } = require("electron");
+
+// sandboxed preloads cannot require api.js, the main process sends its tables
+const { REQUESTS, EVENTS } = ipcRenderer.sendSync("ytdl-api");
+
+const api = {};
+for (const [method, channel] of Object.entries(REQUESTS)) {
+	api[method] = (...args) => ipcRenderer.invoke(channel, ...args);
+}
+
+/**
+ * Listen to an event of EVENTS in api.js
+ * @param {String} event
+ * @param {Function} listener 	called with the payload of the event
+ */
+api.on = (event, listener) => {
+	if (!EVENTS[event]) throw new Error(`unknown event ${event}`);
+	ipcRenderer.on(EVENTS[event], (e, payload) => listener(payload));
+};
+
+contextBridge.exposeInMainWorld("ytdl", api);
@@ -0,0 +1,227 @@
+/**
+ * Format fields rules can filter and sort on
+ */
+const PRESET_FIELDS = {
+	itag: (f) => f.itag,
+	container: (f) => f.container || "",
+	codecs: (f) => f.codecs || "",
+	width: (f) => f.width || 0,
+	height: (f) => f.height || 0,
+	fps: (f) => f.fps || 0,
+	bitrate: (f) => f.bitrate || 0,
+	audioBitrate: (f) => f.audioBitrate || 0,
+	size: (f) => parseInt(f.contentLength, 10) || 0,
+};
+
+const STRING_FIELDS = ["container", "codecs"];
+
+/**
+ * Kinds of formats a selector starts with
+ */
+const KINDS = {
+	video: (f) => f.hasVideo && !f.hasAudio,
+	audio: (f) => f.hasAudio && !f.hasVideo,
+	av: (f) => f.hasVideo && f.hasAudio,
+	any: () => true,
+};
+
+const DEFAULT_SORT = {
+	video: ["-height", "-fps", "-bitrate"],
+	audio: ["-audioBitrate", "-bitrate"],
+	av: ["-height", "-fps", "-bitrate"],
+	any: ["-height", "-fps", "-bitrate", "-audioBitrate"],
+};
+
+/**
+ * Presets offered until the user edits the list, stored as options.presets.
+ * `container` is what pairs are merged into, the merge container of the app
+ * if empty.
+ */
+const DEFAULT_PRESETS = [
+	{
+		name: "Best mp4 up to 1080p",
+		rule: "video[container=mp4][height<=1080] + audio[container=mp4] / av[container=mp4]",
+		container: "mp4",
+	},
+	{ name: "Smallest audio", rule: "audio(+audioBitrate, +size)", container: "" },
+	{ name: "Best opus", rule: "audio[codecs=opus]", container: "" },
+	{
+		name: "720p30 H.264 for old TVs",
+		rule:
+			"av[codecs^=avc1][height<=720][fps<=30] / " +
+			"video[codecs^=avc1][height<=720][fps<=30] + audio[codecs^=mp4a]",
+		container: "mp4",
+	},
+];
+
+/**
+ * Preset used by the Download button and after Get Info, stored as
+ * options.defaultPreset. `auto` queues it as soon as the info is loaded.
+ */
+const DEFAULT_PRESET_SETTINGS = { name: "", auto: false };
+
+const TOKEN = /\s*(?:(\[)\s*(\w+)\s*(<=|>=|!=|\^=|\*=|=|<|>)\s*([^\]]*?)\s*\]|(\()([^)]*)\)|([a-z]+)|([/+]))/y;
+
+/**
+ * Parse a preset rule. A rule lists alternatives separated by /, the first
+ * that matches wins. Each is a selector, or a video and an audio selector
+ * joined by + to merge. A selector is a kind (video, audio, av or any)
+ * followed by filters like [height<=1080] or [codecs^=avc1], and optionally
+ * the order to pick in, like (-fps, +size) where - prefers high values.
+ * @param {String} rule
+ * @returns {Object} { choices } or { error }
+ */
+function parseRule(rule) {
+	const choices = [];
+	let choice = [];
+	let selector;
+	let expect = "kind";
+	TOKEN.lastIndex = 0;
+	const text = `${rule || ""}`.trim();
+	if (!text) return { error: "empty rule" };
+
+	while (TOKEN.lastIndex < text.length) {
+		const at = TOKEN.lastIndex;
+		const m = TOKEN.exec(text);
+		if (!m) return { error: `unexpected "${text.slice(at, at + 10)}"` };
+		const [, bracket, field, op, value, paren, keys, kind, joiner] = m;
+		if (kind) {
+			if (expect !== "kind") return { error: `expected + or / before ${kind}` };
+			if (!KINDS[kind]) return { error: `unknown kind ${kind}, use video, audio, av or any` };
+			selector = { kind, filters: [], sort: DEFAULT_SORT[kind] };
+			choice.push(selector);
+			expect = "filter";
+		} else if (bracket) {
+			if (expect !== "filter") return { error: "a filter must follow a kind" };
+			if (!PRESET_FIELDS[field]) return { error: `unknown field ${field}` };
+			const isString = STRING_FIELDS.includes(field);
+			if (isString && !["=", "!=", "^=", "*="].includes(op)) {
+				return { error: `${field} can only be compared with =, !=, ^= or *=` };
+			}
+			if (!isString && (op === "^=" || op === "*=" || !value || Number.isNaN(Number(value)))) {
+				return { error: `${field} must be compared with a number` };
+			}
+			const operand = isString ? value.toLowerCase() : Number(value);
+			selector.filters.push({ field, op, value: operand });
+		} else if (paren) {
+			if (expect !== "filter") return { error: "an order must follow a kind" };
+			const sort = keys.split(",").map((k) => k.trim());
+			for (const key of sort) {
+				if (!PRESET_FIELDS[key.replace(/^[-+]/, "")]) return { error: `unknown field ${key}` };
+			}
+			selector.sort = sort;
+			expect = "joiner";
+		} else if (joiner) {
+			if (expect === "kind") return { error: `expected a kind before ${joiner}` };
+			if (joiner === "/") {
+				choices.push(choice);
+				choice = [];
+			} else if (choice.length > 1) {
+				return { error: "only two selectors can be merged" };
+			}
+			expect = "kind";
+		}
+	}
+	if (expect === "kind") return { error: "rule ends with + or /" };
+	choices.push(choice);
+
+	for (const c of choices) {
+		if (c.length === 2 && (c[0].kind !== "video" || c[1].kind !== "audio")) {
+			return { error: "merge a video selector with an audio selector, as in video + audio" };
+		}
+	}
+	return { choices };
+}
+
+function matches(format, { field, op, value }) {
+	const actual = PRESET_FIELDS[field](format);
+	const a = typeof actual === "string" ? actual.toLowerCase() : actual;
+	switch (op) {
+		case "=":
+			return a === value;
+		case "!=":
+			return a !== value;
+		case "^=":
+			return a.startsWith(value);
+		case "*=":
+			return a.includes(value);
+		case "<":
+			return a < value;
+		case "<=":
+			return a <= value;
+		case ">":
+			return a > value;
+		default:
+			return a >= value;
+	}
+}
+
+/**
+ * The format a selector picks, if any matches
+ */
+function pick(formats, selector) {
+	const found = formats.filter(
+		(f) => KINDS[selector.kind](f) && selector.filters.every((filter) => matches(f, filter))
+	);
+	const order = selector.sort.map((key) => ({
+		get: PRESET_FIELDS[key.replace(/^[-+]/, "")],
+		sign: key.startsWith("-") ? -1 : 1,
+	}));
+	found.sort((a, b) => {
+		for (const { get, sign } of order) {
+			const x = get(a);
+			const y = get(b);
+			if (x !== y) return (x < y ? -1 : 1) * sign;
+		}
+		return 0;
+	});
+	return found[0];
+}
+
+/**
+ * Formats a rule picks
+ * @param {Array} formats 	info.formats
+ * @param {String} rule 	see parseRule()
+ * @returns {Object} { video, audio } to merge, { format }, undefined if no
+ * 					alternative matches, or { error } if the rule is invalid
+ */
+function selectFormats(formats, rule) {
+	const { choices, error } = parseRule(rule);
+	if (error) return { error };
+	for (const choice of choices) {
+		const picked = choice.map((selector) => pick(formats, selector));
+		if (picked.some((f) => !f)) continue;
+		return picked.length === 2 ? { video: picked[0], audio: picked[1] } : { format: picked[0] };
+	}
+	return undefined;
+}
+
+/**
+ * What formats picked by selectFormats() are, e.g. 1080p mp4 + 128kbps mp4
+ * @param {Object} picked 	{ video, audio } or { format }
+ */
+function pickedLabel({ video, audio, format }) {
+	const label = (f) =>
+		`${f.hasVideo ? f.qualityLabel : `${f.audioBitrate || ""}kbps`} ${f.container}`;
+	return format ? label(format) : `${label(video)} + ${label(audio)}`;
+}
+
+/**
+ * Find a preset by name, ignoring case
+ * @param {Array} presets 	options.presets
+ * @param {String} name
+ */
+function findPreset(presets, name) {
+	const lower = `${name || ""}`.toLowerCase();
+	return (presets || []).find((p) => p.name.toLowerCase() === lower);
+}
+
+module.exports = {
+	PRESET_FIELDS,
+	DEFAULT_PRESETS,
+	DEFAULT_PRESET_SETTINGS,
+	parseRule,
+	selectFormats,
+	pickedLabel,
+	findPreset,
+};
@@ -0,0 +1,195 @@
+const { HttpsProxyAgent } = require("https-proxy-agent");
+const { SocksProxyAgent } = require("socks-proxy-agent");
+const { classify } = require("./retry.js");
+
+/**
+ * Proxy settings, stored as options.proxy. `url` is like
+ * http://proxy:8080 or socks5://host:1080, `username` and `password` are
+ * used for proxies whose URL has no credentials, the app keeps the password
+ * encrypted apart from config.json, `noProxy` lists hosts
+ * reached directly and `pool` more proxies to switch to in turn when one is
+ * rate limited, if `rotate` is set.
+ */
+const DEFAULT_PROXY = {
+	enabled: false,
+	url: "",
+	username: "",
+	password: "",
+	noProxy: "localhost, 127.0.0.1",
+	pool: [],
+	rotate: true,
+};
+
+/**
+ * Proxy URL schemes, socks5h resolves host names on the proxy
+ */
+const PROXY_PROTOCOLS = ["http", "https", "socks5", "socks5h", "socks4"];
+
+/**
+ * What is wrong with a proxy URL, if anything
+ * @param {String} proxy
+ * @returns {String} an error, or empty
+ */
+function checkProxy(proxy) {
+	let url;
+	try {
+		url = new URL(proxy);
+	} catch (err) {
+		return `${proxy} is not a URL`;
+	}
+	if (!PROXY_PROTOCOLS.includes(url.protocol.slice(0, -1))) {
+		return `${url.protocol} proxies are not supported, use ${PROXY_PROTOCOLS.join(", ")}`;
+	}
+	if (!url.hostname) return `${proxy} has no host`;
+	return "";
+}
+
+/**
+ * The proxies of the settings in order, with their credentials
+ * @param {Object} settings 	see DEFAULT_PROXY
+ */
+function proxyList(settings) {
+	const list = [];
+	for (const proxy of [settings.url, ...(settings.pool || [])]) {
+		const text = `${proxy || ""}`.trim();
+		if (!text || checkProxy(text)) continue;
+		const url = new URL(text);
+		if (!url.username && settings.username) {
+			url.username = settings.username;
+			url.password = settings.password || "";
+		}
+		if (!list.includes(url.href)) list.push(url.href);
+	}
+	return list;
+}
+
+/**
+ * Whether a URL is reached without the proxy, `*` bypasses it for every
+ * host and `.example.com` or `example.com` for the domain and subdomains
+ * @param {String} target
+ * @param {String} noProxy 	hosts separated by commas or spaces
+ */
+function bypassed(target, noProxy) {
+	let host;
+	try {
+		host = new URL(target).hostname.toLowerCase();
+	} catch (err) {
+		return false;
+	}
+	return `${noProxy || ""}`
+		.split(/[\s,]+/)
+		.map((h) => h.trim().toLowerCase().replace(/:\d+$/, "").replace(/^\*?\./, ""))
+		.some((h) => h === "*" || (h && (host === h || host.endsWith(`.${h}`))));
+}
+
+/**
+ * The proxies requests go through, shared by everything that fetches so
+ * that a proxy found rate limited is left for the next one everywhere
+ */
+class ProxyPool {
+	constructor() {
+		this.settings = DEFAULT_PROXY;
+		this.list = [];
+		this.index = 0;
+		this.agents = new Map();
+	}
+
+	/**
+	 * Use new settings, starting again from the first proxy if they changed
+	 * @param {Object} settings 	see DEFAULT_PROXY
+	 */
+	configure(settings) {
+		const key = JSON.stringify(settings);
+		if (key === this.key) return;
+		this.key = key;
+		this.settings = { ...DEFAULT_PROXY, ...settings };
+		this.list = this.settings.enabled ? proxyList(this.settings) : [];
+		this.index = 0;
+		this.agents.clear();
+	}
+
+	/**
+	 * The proxy to reach a URL through, undefined to connect directly
+	 * @param {String} target
+	 */
+	proxyFor(target) {
+		if (!this.list.length || bypassed(target, this.settings.noProxy)) return undefined;
+		return this.list[this.index];
+	}
+
+	agentFor(proxy) {
+		if (!this.agents.has(proxy)) {
+			const agent = proxy.startsWith("socks")
+				? new SocksProxyAgent(proxy)
+				: new HttpsProxyAgent(proxy);
+			this.agents.set(proxy, agent);
+		}
+		return this.agents.get(proxy);
+	}
+
+	/**
+	 * Options for http.request(), miniget and ytdl's requestOptions
+	 * @param {String} target
+	 * @returns {Object} { agent } or nothing to connect directly
+	 */
+	requestOptions(target) {
+		const proxy = this.proxyFor(target);
+		return proxy ? { agent: this.agentFor(proxy) } : {};
+	}
+
+	/**
+	 * Move on to the next proxy if a request through `proxy` was rate
+	 * limited, unless another request did already
+	 * @param {Error} err
+	 * @param {String} proxy 	what proxyFor() returned for the request
+	 */
+	failed(err, proxy) {
+		if (!proxy || !this.settings.rotate || this.list.length < 2) return;
+		if (classify(err) !== "rateLimited" || this.list[this.index] !== proxy) return;
+		this.index = (this.index + 1) % this.list.length;
+	}
+
+	/**
+	 * Run a request, trying the next proxy of the pool each time one is
+	 * rate limited until every proxy was tried
+	 * @param {String} target 	the URL requested
+	 * @param {Function} task 	called with the request options, see
+	 * 							requestOptions(), returns a promise
+	 */
+	async attempt(target, task) {
+		for (let tries = 1; ; tries++) {
+			const proxy = this.proxyFor(target);
+			try {
+				return await task(proxy ? { agent: this.agentFor(proxy) } : {});
+			} catch (err) {
+				this.failed(err, proxy);
+				const next = this.proxyFor(target);
+				if (!proxy || next === proxy || tries >= this.list.length) throw err;
+			}
+		}
+	}
+
+	/**
+	 * Environment variables giving ffmpeg the proxy to read a URL through.
+	 * ffmpeg only supports plain http:// proxies, others throw rather than
+	 * letting it connect directly.
+	 * @param {String} target
+	 */
+	ffmpegEnv(target) {
+		const proxy = this.proxyFor(target);
+		if (!proxy) return {};
+		const scheme = new URL(proxy).protocol.slice(0, -1);
+		if (scheme !== "http") {
+			throw new Error(`ffmpeg cannot cut clips through ${scheme} proxies, only http ones`);
+		}
+		return { http_proxy: proxy, https_proxy: proxy };
+	}
+}
+
+/**
+ * The pool of the process, configured from the options by index.js and
+ * the command line
+ */
+const proxies = new ProxyPool();
+
+module.exports = { DEFAULT_PROXY, PROXY_PROTOCOLS, checkProxy, bypassed, proxies };
@@ -0,0 +1,177 @@
+const EventEmitter = require("events");
+
+/**
+ * Possible states of a job in the queue
+ */
+const STATES = ["pending", "active", "paused", "done", "failed"];
+
+/**
+ * A download queue processing at most `maxConcurrent` jobs at a time.
+ *
+ * `worker(job, progress, signal)` must return a promise that settles once the
+ * job is finished. `progress(received, total)` reports transferred bytes and
+ * `signal` emits "abort" with "pause" or "remove" when the job is paused or
+ * removed while active.
+ *
+ * Emits "change" when a job is added, removed or changes state (use it to
+ * persist the queue), "progress" when an active job reports progress,
+ * "finish" when a job is done or has failed, and "remove" with the job, in
+ * the state it had, when a job is removed.
+ */
+class DownloadQueue extends EventEmitter {
+	constructor(worker, maxConcurrent = 2) {
+		super();
+		this.worker = worker;
+		this.maxConcurrent = maxConcurrent;
+		this.jobs = [];
+		this.signals = new Map();
+		this.nextId = 1;
+	}
+
+	/**
+	 * Add a job to the end of the queue
+	 * @param {Object} job 	must contain at least url and itag
+	 */
+	add(job) {
+		const entry = {
+			...job,
+			id: this.nextId++,
+			state: "pending",
+			received: 0,
+			total: 0,
+			error: "",
+		};
+		this.jobs.push(entry);
+		this.emit("change", entry);
+		this.schedule();
+		return entry;
+	}
+
+	get(id) {
+		return this.jobs.find((j) => j.id === id);
+	}
+
+	pause(id) {
+		const job = this.get(id);
+		if (!job || (job.state !== "pending" && job.state !== "active")) return;
+		this.abort(job, "pause");
+		this.setState(job, "paused");
+	}
+
+	resume(id) {
+		const job = this.get(id);
+		if (!job || (job.state !== "paused" && job.state !== "failed")) return;
+		job.error = "";
+		this.setState(job, "pending");
+	}
+
+	remove(id) {
+		const job = this.get(id);
+		if (!job) return;
+		this.abort(job, "remove");
+		this.jobs = this.jobs.filter((j) => j !== job);
+		this.emit("remove", job);
+		this.emit("change", job);
+		this.schedule();
+	}
+
+	/**
+	 * Remove every finished job from the queue
+	 */
+	clearDone() {
+		this.jobs = this.jobs.filter((j) => j.state !== "done");
+		this.emit("change");
+	}
+
+	setMaxConcurrent(n) {
+		this.maxConcurrent = Math.max(1, parseInt(n, 10) || 1);
+		this.schedule();
+	}
+
+	setState(job, state) {
+		job.state = state;
+		this.emit("change", job);
+		this.schedule();
+	}
+
+	abort(job, reason) {
+		const signal = this.signals.get(job.id);
+		if (!signal) return;
+		this.signals.delete(job.id);
+		signal.aborted = true;
+		signal.reason = reason;
+		signal.emit("abort", reason);
+	}
+
+	/**
+	 * Start pending jobs until the concurrency limit is reached
+	 */
+	schedule() {
+		let active = this.jobs.filter((j) => j.state === "active").length;
+		for (const job of this.jobs) {
+			if (active >= this.maxConcurrent) break;
+			if (job.state !== "pending") continue;
+			active++;
+			this.start(job);
+		}
+	}
+
+	start(job) {
+		const signal = new EventEmitter();
+		signal.aborted = false;
+		this.signals.set(job.id, signal);
+		job.state = "active";
+		this.emit("change", job);
+
+		const progress = (received, total) => {
+			if (signal.aborted) return;
+			job.received = received;
+			job.total = total;
+			this.emit("progress", job);
+		};
+
+		Promise.resolve()
+			.then(() => this.worker(job, progress, signal))
+			.then(
+				() => {
+					if (signal.aborted) return;
+					this.signals.delete(job.id);
+					this.setState(job, "done");
+					this.emit("finish", job);
+				},
+				(err) => {
+					if (signal.aborted) return;
+					this.signals.delete(job.id);
+					job.error = err && err.message ? err.message : `${err}`;
+					this.setState(job, "failed");
+					this.emit("finish", job);
+				}
+			);
+	}
+
+	/**
+	 * Serializable snapshot of the queue
+	 */
+	toJSON() {
+		return this.jobs.map((j) => ({ ...j }));
+	}
+
+	/**
+	 * Load jobs saved with toJSON(). Jobs that were running when the app was
+	 * closed go back to pending.
+	 * @param {Array} jobs
+	 */
+	restore(jobs) {
+		if (!Array.isArray(jobs)) return;
+		for (const j of jobs) {
+			if (!STATES.includes(j.state)) continue;
+			if (j.state === "active") j.state = "pending";
+			this.jobs.push(j);
+			this.nextId = Math.max(this.nextId, j.id + 1);
+		}
+		this.emit("change");
+		this.schedule();
+	}
+}
+
+module.exports = { DownloadQueue, STATES };
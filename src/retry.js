@@ -0,0 +1,110 @@
+/**
+ * Default retry policy, stored as options.retry
+ */
+const DEFAULT_RETRY = {
+	maxAttempts: 5,
+	baseDelay: 2, // seconds
+	maxDelay: 120, // seconds
+	jitter: 0.3, // fraction of the delay added or removed at random
+	retryOn: { forbidden: true, rateLimited: true, server: true, network: true },
+};
+
+/**
+ * Human readable names of the error classes in retryOn
+ */
+const ERROR_CLASSES = {
+	forbidden: "403 Forbidden",
+	rateLimited: "429 Too Many Requests",
+	server: "5xx server errors",
+	network: "Network errors",
+};
+
+const NETWORK_CODES = [
+	"ECONNRESET",
+	"ECONNREFUSED",
+	"ECONNABORTED",
+	"ETIMEDOUT",
+	"ENOTFOUND",
+	"EAI_AGAIN",
+	"EPIPE",
+	"ENETUNREACH",
+	"EHOSTUNREACH",
+];
+
+/**
+ * Sort an error into one of the classes of ERROR_CLASSES, or "fatal" for
+ * errors that retrying will not fix (missing formats, disk errors...)
+ * @param {Error} err
+ */
+function classify(err) {
+	if (!err) return "fatal";
+	const status =
+		err.statusCode || parseInt((/Status code: (\d+)/.exec(err.message) || [])[1], 10);
+	if (status === 403) return "forbidden";
+	if (status === 429) return "rateLimited";
+	if (status >= 500 && status < 600) return "server";
+	if (NETWORK_CODES.includes(err.code)) return "network";
+	if (/ended early|socket hang up|timeout/i.test(err.message)) return "network";
+	return "fatal";
+}
+
+/**
+ * Seconds to wait before the given attempt (the first retry is attempt 2)
+ * @param {Number} attempt
+ * @param {Object} policy
+ */
+function backoffDelay(attempt, policy) {
+	const delay = Math.min(policy.maxDelay, policy.baseDelay * 2 ** (attempt - 2));
+	const jitter = delay * policy.jitter * (Math.random() * 2 - 1);
+	return Math.max(0, delay + jitter);
+}
+
+function sleep(seconds, signal) {
+	return new Promise((resolve) => {
+		const done = () => {
+			clearTimeout(timer);
+			signal.removeListener("abort", done);
+			resolve();
+		};
+		const timer = setTimeout(done, seconds * 1000);
+		signal.once("abort", done);
+	});
+}
+
+/**
+ * Wrap a DownloadQueue worker so that failed attempts are retried according
+ * to a policy. The job gets `attempt`, `retryAt` and `lastError` fields which
+ * are reported through the progress callback while waiting.
+ * @param {Function} worker 	a DownloadQueue worker
+ * @param {Function} getPolicy 	returns the current retry policy
+ */
+function withRetry(worker, getPolicy) {
+	return async (job, progress, signal) => {
+		for (let attempt = 1; ; attempt++) {
+			const policy = { ...DEFAULT_RETRY, ...getPolicy() };
+			job.attempt = attempt;
+			job.retryAt = 0;
+			try {
+				return await worker(job, progress, signal);
+			} catch (err) {
+				const kind = classify(err);
+				if (
+					signal.aborted ||
+					kind === "fatal" ||
+					!policy.retryOn[kind] ||
+					attempt >= policy.maxAttempts
+				) {
+					throw err;
+				}
+				const delay = backoffDelay(attempt + 1, policy);
+				job.lastError = err.message || `${err}`;
+				job.retryAt = Date.now() + delay * 1000;
+				progress(job.received, job.total);
+				await sleep(delay, signal);
+				if (signal.aborted) return;
+			}
+		}
+	};
+}
+
+module.exports = { DEFAULT_RETRY, ERROR_CLASSES, classify, backoffDelay, withRetry };
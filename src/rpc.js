@@ -0,0 +1,246 @@
+const http = require("http");
+const crypto = require("crypto");
+const { describeFormats } = require("./downloader.js");
+const { RequestError } = require("./api.js");
+
+/**
+ * Settings of the local API, stored as options.rpc. It is off until enabled
+ * in Settings, only listens on 127.0.0.1 and every call needs the token.
+ */
+const DEFAULT_RPC = { enabled: false, port: 7314, token: "" };
+
+const MAX_BODY = 1 << 20;
+
+/**
+ * JSON-RPC 2.0 error codes
+ */
+const ERRORS = {
+	parse: -32700,
+	invalidRequest: -32600,
+	methodNotFound: -32601,
+	invalidParams: -32602,
+	failed: -32000,
+};
+
+class RpcError extends Error {
+	constructor(code, message) {
+		super(message);
+		this.code = code;
+	}
+}
+
+/**
+ * A random token for the local API
+ */
+function newToken() {
+	return crypto.randomBytes(24).toString("hex");
+}
+
+const requireUrl = (params) => {
+	if (typeof params.url !== "string") throw new RpcError(ERRORS.invalidParams, "url is required");
+	return params.url;
+};
+
+const requireId = (params) => {
+	if (!Number.isInteger(params.id)) throw new RpcError(ERRORS.invalidParams, "id is required");
+	return params.id;
+};
+
+/**
+ * Methods of the API, called with the service and the named params
+ *
+ * - info { url, refresh } → { videoId, title, author, lengthSeconds, chapters,
+ *   subtitles, playlistUrl, formats }
+ * - formats { url, refresh } → [Format], see describeFormats() in downloader.js
+ *   where refresh fetches the info even if it is cached
+ * - enqueue { url, format, preset, container, audioFormat, saveDir,
+ *   template, ignoreArchive } → [Job], see queueUrl() in service.js
+ * - queue → [Job]
+ * - pause { id }, resume { id }, cancel { id }
+ */
+const METHODS = {
+	async info(service, params) {
+		const info = await service.getInfo(requireUrl(params), !!params.refresh);
+		const details = info.videoDetails;
+		return {
+			videoId: details.videoId,
+			title: details.title,
+			author: details.author && details.author.name,
+			lengthSeconds: parseInt(details.lengthSeconds, 10) || 0,
+			chapters: info.chapters,
+			subtitles: info.tracks.map((t) => t.languageCode),
+			playlistUrl: info.playlistUrl || "",
+			formats: describeFormats(info.formats),
+		};
+	},
+	async formats(service, params) {
+		const info = await service.getInfo(requireUrl(params), !!params.refresh);
+		return describeFormats(info.formats);
+	},
+	enqueue(service, params) {
+		return service.queueUrl(requireUrl(params), params);
+	},
+	queue(service) {
+		return service.getQueue();
+	},
+	pause(service, params) {
+		service.pause(requireId(params));
+		return null;
+	},
+	resume(service, params) {
+		service.resume(requireId(params));
+		return null;
+	},
+	cancel(service, params) {
+		service.remove(requireId(params));
+		return null;
+	},
+};
+
+/**
+ * Answer one JSON-RPC request
+ * @returns {Promise<Object>} the response, or undefined for notifications
+ */
+async function call(service, request) {
+	const id = request && request.id !== undefined ? request.id : null;
+	// notifications get no reply
+	const notification = request && request.id === undefined;
+	const reply = (fields) => (notification ? undefined : { jsonrpc: "2.0", id, ...fields });
+	try {
+		if (!request || request.jsonrpc !== "2.0" || typeof request.method !== "string") {
+			throw new RpcError(ERRORS.invalidRequest, "invalid request");
+		}
+		const method = Object.prototype.hasOwnProperty.call(METHODS, request.method)
+			? METHODS[request.method]
+			: undefined;
+		if (!method) throw new RpcError(ERRORS.methodNotFound, `unknown method ${request.method}`);
+		const params = request.params === undefined ? {} : request.params;
+		if (typeof params !== "object" || params === null || Array.isArray(params)) {
+			throw new RpcError(ERRORS.invalidParams, "params must be an object");
+		}
+		return reply({ result: await method(service, params) });
+	} catch (err) {
+		const code =
+			err instanceof RpcError
+				? err.code
+				: err instanceof RequestError
+				? ERRORS.invalidParams
+				: ERRORS.failed;
+		return reply({ error: { code, message: `${err.message || err}` } });
+	}
+}
+
+/**
+ * Whether a request carries the token, compared in constant time
+ */
+function authorized(req, token) {
+	const match = /^Bearer (.+)$/.exec(req.headers.authorization || "");
+	if (!token || !match) return false;
+	const given = Buffer.from(match[1]);
+	const expected = Buffer.from(token);
+	return given.length === expected.length && crypto.timingSafeEqual(given, expected);
+}
+
+/**
+ * JSON-RPC 2.0 over HTTP for browser extensions and scripts, POSTed to
+ * http://127.0.0.1:<port>/rpc with an `Authorization: Bearer <token>` header.
+ * Batches are supported, params are named.
+ */
+class RpcServer {
+	/**
+	 * @param {DownloadService} service
+	 */
+	constructor(service) {
+		this.service = service;
+		this.server = undefined;
+	}
+
+	/**
+	 * Listen with new settings, or stop when they are disabled
+	 * @param {Object} rpc 	{ enabled, port, token }
+	 * @returns {Promise} rejects if the port cannot be used
+	 */
+	async configure(rpc) {
+		const key = JSON.stringify(rpc);
+		if (key === this.key) return;
+		await this.stop();
+		this.key = key;
+		if (!rpc.enabled || !rpc.token) return;
+		this.settings = rpc;
+		const server = http.createServer((req, res) => this.handle(req, res));
+		await new Promise((resolve, reject) => {
+			server.once("error", (err) => {
+				this.key = undefined;
+				reject(err);
+			});
+			server.listen(rpc.port, "127.0.0.1", resolve);
+		});
+		this.server = server;
+	}
+
+	stop() {
+		const server = this.server;
+		this.server = undefined;
+		this.key = undefined;
+		if (!server) return Promise.resolve();
+		return new Promise((resolve) => server.close(() => resolve()));
+	}
+
+	handle(req, res) {
+		const send = (status, body) => {
+			res.writeHead(status, {
+				"Content-Type": "application/json",
+				// extensions call from their own origin, the token guards the rest
+				"Access-Control-Allow-Origin": "*",
+				"Access-Control-Allow-Headers": "Authorization, Content-Type",
+				"Access-Control-Allow-Methods": "POST",
+			});
+			res.end(body === undefined ? "" : JSON.stringify(body));
+		};
+		// pages on other hosts resolving to 127.0.0.1 must not get through
+		const { port } = this.settings;
+		if (![`127.0.0.1:${port}`, `localhost:${port}`].includes(req.headers.host)) {
+			return send(403, { error: "forbidden host" });
+		}
+		if (req.url !== "/rpc") return send(404, { error: "not found" });
+		if (req.method === "OPTIONS") return send(204);
+		if (req.method !== "POST") return send(405, { error: "use POST" });
+		if (!authorized(req, this.settings.token)) return send(401, { error: "bad token" });
+
+		const chunks = [];
+		let size = 0;
+		req.on("data", (chunk) => {
+			size += chunk.length;
+			if (size > MAX_BODY) {
+				send(413, { error: "request too large" });
+				req.destroy();
+				return;
+			}
+			chunks.push(chunk);
+		});
+		req.on("end", async () => {
+			let body;
+			try {
+				body = JSON.parse(Buffer.concat(chunks).toString("utf8"));
+			} catch (err) {
+				const error = { code: ERRORS.parse, message: "parse error" };
+				send(200, { jsonrpc: "2.0", id: null, error });
+				return;
+			}
+			if (Array.isArray(body)) {
+				if (!body.length) {
+					send(200, await call(this.service, undefined));
+					return;
+				}
+				const replies = await Promise.all(body.map((r) => call(this.service, r)));
+				const answered = replies.filter((r) => r !== undefined);
+				send(answered.length ? 200 : 204, answered.length ? answered : undefined);
+				return;
+			}
+			const reply = await call(this.service, body);
+			send(reply ? 200 : 204, reply);
+		});
+	}
+}
+
+module.exports = { DEFAULT_RPC, newToken, RpcServer };
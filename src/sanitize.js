@@ -0,0 +1,159 @@
+/**
+ * Filename sanitization for the filesystems downloads end up on.
+ */
+
+const WINDOWS_FORBIDDEN = /[<>:"/\\|?*\u0000-\u001f]/g;
+const WINDOWS_RESERVED = /^(con|prn|aux|nul|com[1-9]|lpt[1-9])(\..*)?$/i;
+
+/**
+ * Sanitization profiles. `maxBytes` is the longest UTF-8 filename allowed and
+ * `maxFileSize` the largest file the filesystem can hold.
+ */
+const PROFILES = {
+	posix: {
+		label: "POSIX (Linux, macOS)",
+		forbidden: /[/\u0000-\u001f\u007f]/g,
+		windows: false,
+		ascii: false,
+		maxBytes: 255,
+	},
+	windows: {
+		label: "Windows (NTFS)",
+		forbidden: WINDOWS_FORBIDDEN,
+		windows: true,
+		ascii: false,
+		maxBytes: 255,
+	},
+	fat32: {
+		label: "FAT32 (USB drives, SD cards)",
+		forbidden: WINDOWS_FORBIDDEN,
+		windows: true,
+		ascii: false,
+		maxBytes: 255,
+		maxFileSize: 4 * 1024 * 1024 * 1024 - 1,
+	},
+	ascii: {
+		label: "ASCII only",
+		forbidden: WINDOWS_FORBIDDEN,
+		windows: true,
+		ascii: true,
+		maxBytes: 255,
+	},
+};
+
+/**
+ * What to do when the file a download would be saved to already exists
+ */
+const COLLISIONS = {
+	number: "Add a number",
+	skip: "Skip the download",
+	overwrite: "Overwrite",
+	ask: "Ask",
+};
+
+/**
+ * Default profile for the platform the app runs on
+ */
+function defaultProfile() {
+	return process.platform === "win32" ? "windows" : "posix";
+}
+
+/**
+ * Bytes reserved at the end of filenames for temporary suffixes such as
+ * `.f137.part.json`
+ */
+const SUFFIX_RESERVE = 16;
+
+/**
+ * Cut a string to at most `maxBytes` bytes of UTF-8 without splitting a
+ * character
+ * @param {String} str
+ * @param {Number} maxBytes
+ */
+function truncateBytes(str, maxBytes) {
+	if (Buffer.byteLength(str, "utf8") <= maxBytes) return str;
+	let out = "";
+	let bytes = 0;
+	for (const char of str) {
+		const size = Buffer.byteLength(char, "utf8");
+		if (bytes + size > maxBytes) break;
+		out += char;
+		bytes += size;
+	}
+	return out;
+}
+
+/**
+ * Replace non-ASCII characters, dropping accents where possible
+ */
+function toAscii(str) {
+	return str
+		.normalize("NFKD")
+		.replace(/[\u0300-\u036f]/g, "")
+		.replace(/[^\x20-\x7e]/g, "_");
+}
+
+/**
+ * Make one path component safe for a profile
+ * @param {String} name
+ * @param {String} profileName 	a key of PROFILES
+ * @param {Boolean} isFile 	keep the extension when truncating and leave room
+ * 							for temporary suffixes
+ */
+function sanitizeComponent(name, profileName, isFile = false) {
+	const profile = PROFILES[profileName] || PROFILES[defaultProfile()];
+	let out = name.normalize("NFC").replace(profile.forbidden, "_");
+	if (profile.ascii) out = toAscii(out);
+	if (profile.windows) {
+		out = out.replace(/[. ]+$/, "");
+		if (WINDOWS_RESERVED.test(out)) out = `_${out}`;
+	}
+	out = out.trim();
+	if (!out || out === "." || out === "..") out = "_";
+
+	const maxBytes = isFile ? profile.maxBytes - SUFFIX_RESERVE : profile.maxBytes;
+	if (Buffer.byteLength(out, "utf8") > maxBytes) {
+		const dot = isFile ? out.lastIndexOf(".") : -1;
+		const ext = dot > 0 && out.length - dot <= 10 ? out.slice(dot) : "";
+		const stem = truncateBytes(out.slice(0, out.length - ext.length), maxBytes - ext.length);
+		out = (profile.windows ? stem.replace(/[. ]+$/, "") : stem) + ext;
+	}
+	return out;
+}
+
+/**
+ * Sanitize every component of a relative path using / as the separator
+ * @param {String} relative
+ * @param {String} profileName 	a key of PROFILES
+ */
+function sanitizePath(relative, profileName) {
+	const components = relative.split("/");
+	return components
+		.map((c, i) => sanitizeComponent(c, profileName, i === components.length - 1))
+		.join("/");
+}
+
+/**
+ * `name (n).ext` for the n-th alternative of a filename, shortening the name
+ * so that the number fits next to the temporary suffixes
+ * @param {String} filename 	a file name sanitized with sanitizeComponent()
+ * @param {Number} n
+ * @param {String} profileName 	a key of PROFILES
+ */
+function numbered(filename, n, profileName) {
+	const profile = PROFILES[profileName] || PROFILES[defaultProfile()];
+	const m = /^(.*?)(\.[^./\\]{1,10})?$/.exec(filename);
+	const tail = ` (${n})${m[2] || ""}`;
+	const stem = truncateBytes(m[1], profile.maxBytes - SUFFIX_RESERVE - Buffer.byteLength(tail));
+	return (profile.windows ? stem.replace(/[. ]+$/, "") : stem) + tail;
+}
+
+module.exports = {
+	PROFILES,
+	COLLISIONS,
+	defaultProfile,
+	truncateBytes,
+	sanitizeComponent,
+	sanitizePath,
+	numbered,
+};
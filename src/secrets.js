@@ -0,0 +1,37 @@
+const fs = require("fs");
+const path = require("path");
+
+/**
+ * Save a secret encrypted, see safeStorage in Electron
+ * @param {String} file
+ * @param {String} secret
+ * @param {Function} encrypt 	string => Buffer
+ */
+function saveSecret(file, secret, encrypt) {
+	fs.mkdirSync(path.dirname(file), { recursive: true });
+	fs.writeFileSync(file, encrypt(secret), { mode: 0o600 });
+}
+
+/**
+ * Read a secret saved by saveSecret()
+ * @param {String} file
+ * @param {Function} decrypt 	Buffer => string
+ * @returns {String} the secret, or empty if there is none or it cannot be
+ * 					decrypted
+ */
+function loadSecret(file, decrypt) {
+	try {
+		return decrypt(fs.readFileSync(file));
+	} catch (err) {
+		if (err.code !== "ENOENT") console.error(err);
+		return "";
+	}
+}
+
+function removeSecret(file) {
+	try {
+		fs.unlinkSync(file);
+	} catch (err) {}
+}
+
+module.exports = { saveSecret, loadSecret, removeSecret };
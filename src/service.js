@@ -0,0 +1,688 @@
+const EventEmitter = require("events");
+const fs = require("fs");
+const path = require("path");
+const { DownloadQueue } = require("./queue.js");
+const {
+	bestPair,
+	downloadJob,
+	discardPartial,
+	extensionOf,
+} = require("./downloader.js");
+const { CONTAINERS, AUDIO_CODECS, DEFAULT_CONVERT } = require("./ffmpeg.js");
+const { DEFAULT_RETRY, ERROR_CLASSES, withRetry } = require("./retry.js");
+const { historyRecord, searchHistory } = require("./history.js");
+const { DEFAULT_ARCHIVE, Archive } = require("./archive.js");
+const { PREFERENCES, isPlaylistUrl, playlistOf, getPlaylist } = require("./playlist.js");
+const { DEFAULT_EMBED } = require("./metadata.js");
+const { DEFAULT_SUBTITLES, SUBTITLE_FORMATS, listTracks } = require("./subtitles.js");
+const { DEFAULT_CHAPTERS, CHAPTER_MODES, chaptersOf } = require("./chapters.js");
+const { DEFAULT_CLIP, parseClip, clipLabel } = require("./clip.js");
+const { DEFAULT_LINKS } = require("./links.js");
+const { DEFAULT_RPC } = require("./rpc.js");
+const {
+	PRESET_FIELDS,
+	DEFAULT_PRESETS,
+	DEFAULT_PRESET_SETTINGS,
+	parseRule,
+	selectFormats,
+	pickedLabel,
+	findPreset,
+} = require("./presets.js");
+const { DEFAULT_SUBSCRIPTIONS } = require("./subscriptions.js");
+const { DEFAULT_BATCH, parseBatch, mapLimit } = require("./batch.js");
+const { DEFAULT_INFO_CACHE, fetchInfo } = require("./info-cache.js");
+const { DEFAULT_PROXY, checkProxy } = require("./proxy.js");
+const { RequestError, FormatError } = require("./api.js");
+const { PROFILES, COLLISIONS, defaultProfile, sanitizePath } = require("./sanitize.js");
+const {
+	DEFAULT_TEMPLATE,
+	DEFAULT_PLAYLIST_TEMPLATE,
+	DEFAULT_CHAPTER_TEMPLATE,
+	FIELDS,
+	validateTemplate,
+	templateValues,
+	renderTemplate,
+} = require("./template.js");
+
+const labels = (table) => {
+	const out = {};
+	for (const [key, value] of Object.entries(table)) out[key] = value.label;
+	return out;
+};
+
+/**
+ * Tables and defaults the windows display, without the parts that only the
+ * engine needs
+ */
+function constants() {
+	const audioCodecs = {};
+	for (const [key, c] of Object.entries(AUDIO_CODECS)) {
+		audioCodecs[key] = { label: c.label, ext: c.ext, quality: c.quality };
+	}
+	return {
+		containers: Object.keys(CONTAINERS),
+		audioCodecs,
+		subtitleFormats: labels(SUBTITLE_FORMATS),
+		profiles: labels(PROFILES),
+		collisions: COLLISIONS,
+		errorClasses: ERROR_CLASSES,
+		preferences: PREFERENCES,
+		chapterModes: CHAPTER_MODES,
+		fields: FIELDS,
+		presetFields: Object.keys(PRESET_FIELDS),
+		defaults: {
+			convert: DEFAULT_CONVERT,
+			embed: DEFAULT_EMBED,
+			subtitles: DEFAULT_SUBTITLES,
+			chapters: DEFAULT_CHAPTERS,
+			clip: DEFAULT_CLIP,
+			links: DEFAULT_LINKS,
+			rpc: DEFAULT_RPC,
+			presets: DEFAULT_PRESETS,
+			defaultPreset: DEFAULT_PRESET_SETTINGS,
+			subscriptions: DEFAULT_SUBSCRIPTIONS,
+			batch: DEFAULT_BATCH,
+			infoCache: DEFAULT_INFO_CACHE,
+			proxy: DEFAULT_PROXY,
+			retry: DEFAULT_RETRY,
+			archive: DEFAULT_ARCHIVE,
+			template: DEFAULT_TEMPLATE,
+			playlistTemplate: DEFAULT_PLAYLIST_TEMPLATE,
+			chapterTemplate: DEFAULT_CHAPTER_TEMPLATE,
+			sanitize: defaultProfile(),
+		},
+	};
+}
+
+/**
+ * The parts of ytdl.getInfo() the app uses, small enough to send over IPC
+ * @param {Object} info 	result of ytdl.getInfo()
+ * @param {String} url 	the URL the info was asked for
+ * @returns {Object} { videoDetails, formats, player_response, response,
+ * 					bestPairs, tracks, translations, chapters, playlistUrl }
+ * 					where player_response only keeps the captions, response
+ * 					only the player overlays used for chapters, bestPairs are
+ * 					bestPair() by container, tracks and translations come
+ * 					from listTracks() and playlistUrl is set for videos
+ * 					opened from a playlist
+ */
+function summarizeInfo(info, url = "") {
+	const bestPairs = {};
+	for (const container of Object.keys(CONTAINERS)) {
+		bestPairs[container] = bestPair(info.formats, container);
+	}
+	return {
+		videoDetails: info.videoDetails,
+		formats: info.formats,
+		player_response: { captions: info.player_response && info.player_response.captions },
+		response: { playerOverlays: info.response && info.response.playerOverlays },
+		bestPairs,
+		...listTracks(info),
+		chapters: chaptersOf(info),
+		playlistUrl: playlistOf(info.videoDetails.video_url) || playlistOf(url),
+	};
+}
+
+/**
+ * Audio conversion settings of a request, see queueUrl()
+ */
+function convertOf(options, request) {
+	return { ...options.convert, codec: request.audioFormat || options.convert.codec };
+}
+
+/**
+ * Check a request, see queueUrl()
+ * @param {Object} request
+ * @returns {String} its format, "merged" if it has none
+ * @throws {RequestError} if it asks for something unknown
+ */
+function checkRequest(request) {
+	const format = `${request.format || "merged"}`;
+	if (!PREFERENCES[format] && !/^\d+(\+\d+)?$/.test(format)) {
+		throw new RequestError(`unknown format ${format}`);
+	}
+	if (request.container && !CONTAINERS[request.container]) {
+		throw new RequestError(`unknown container ${request.container}`);
+	}
+	const codec = request.audioFormat;
+	if (codec && codec !== "original" && !AUDIO_CODECS[codec]) {
+		throw new RequestError(`unknown audio format ${codec}`);
+	}
+	const templateError = request.template && validateTemplate(`${request.template}`);
+	if (templateError) throw new RequestError(templateError);
+	return format;
+}
+
+/**
+ * The request a window makes with a JobIntent, see queueUrl()
+ * @param {Object} intent 	see JobIntent in api.js
+ */
+function intentRequest(intent) {
+	const request = { ignoreArchive: intent.ignoreArchive === true };
+	if (intent.itag !== undefined) {
+		const itags = intent.audioItag === undefined ? [intent.itag] : [intent.itag, intent.audioItag];
+		if (!itags.every(Number.isInteger)) throw new RequestError("itags must be numbers");
+		return { ...request, format: itags.join("+"), audioFormat: intent.audioFormat };
+	}
+	if (intent.preset !== undefined) return { ...request, preset: `${intent.preset}` };
+	return { ...request, format: intent.preference };
+}
+
+/**
+ * Position of a playlist entry sent by a window
+ * @param {Object} playlist 	{ title, index, count }, or undefined
+ */
+function intentPlaylist(playlist) {
+	if (playlist === undefined) return undefined;
+	const { title, index, count } = playlist || {};
+	if (!Number.isInteger(index) || !Number.isInteger(count) || index < 1 || index > count) {
+		throw new RequestError("invalid playlist position");
+	}
+	return { title: `${title || ""}`, index, count };
+}
+
+/**
+ * Subtitle settings of a job for the tracks a window picked
+ * @param {Object} options 	the app options
+ * @param {Object} subtitles 	{ tracks, translateTo }, or undefined
+ */
+function intentSubtitles(options, subtitles) {
+	if (!subtitles || !Array.isArray(subtitles.tracks)) return undefined;
+	const tracks = subtitles.tracks.filter((t) => typeof t === "string");
+	if (!tracks.length) return undefined;
+	const { translateTo } = subtitles;
+	return {
+		...options.subtitles,
+		tracks,
+		translateTo: typeof translateTo === "string" && translateTo ? translateTo : undefined,
+	};
+}
+
+/**
+ * Job to queue for a video or a playlist entry, with the settings of the app
+ * unless the request overrides them
+ * @param {Object} options 	the app options
+ * @param {Object} request 	see queueUrl()
+ * @param {Object} fields 	{ url, title, playlist }
+ */
+function requestSpec(options, request, fields) {
+	const preset = request.preset && findPreset(options.presets, request.preset);
+	if (request.preset && !preset) throw new RequestError(`unknown preset ${request.preset}`);
+	const container = request.container || options.mergeContainer || "mkv";
+	const spec = {
+		...fields,
+		embed: options.embed,
+		chapters: { ...options.chapters, template: options.chapterTemplate },
+		template:
+			request.template || (fields.playlist ? options.playlistTemplate : options.template),
+		saveDir: path.resolve(request.saveDir || options.saveDir),
+		ignoreArchive: !!request.ignoreArchive,
+	};
+	if (preset) {
+		return { ...spec, preset, container: request.container || preset.container || container };
+	}
+	if (PREFERENCES[request.format]) {
+		return {
+			...spec,
+			preference: request.format,
+			container: request.format === "merged" ? container : "",
+			convert: request.format === "audio" ? convertOf(options, request) : undefined,
+		};
+	}
+	const [itag, audioItag] = request.format.split("+").map((n) => parseInt(n, 10));
+	return { ...spec, itag, audioItag, container: audioItag ? container : "" };
+}
+
+/**
+ * What a batch row asks for on top of the request of the whole batch
+ * @param {Object} request 	see queueUrl()
+ * @param {Object} row 	from parseBatch() in batch.js
+ * @throws {RequestError} if the row names a folder outside the save location
+ */
+function rowRequest(request, row) {
+	const req = { ...request };
+	if (PREFERENCES[row.preset] || /^\d+(\+\d+)?$/.test(row.preset)) {
+		req.format = row.preset;
+		req.preset = undefined;
+	} else if (row.preset) {
+		req.preset = row.preset;
+	}
+	// folders are inside the save location of the batch, rows cannot write
+	// anywhere else
+	if (row.saveDir) {
+		const base = path.resolve(request.saveDir || "");
+		req.saveDir = path.resolve(base, row.saveDir);
+		const relative = path.relative(base, req.saveDir);
+		if (relative === ".." || relative.startsWith(`..${path.sep}`) || path.isAbsolute(relative)) {
+			throw new RequestError(`${row.saveDir} is outside the save location`);
+		}
+	}
+	return req;
+}
+
+/**
+ * Fill in the container of a job asked by itag, converting audio-only
+ * formats like the app does
+ * @param {Object} spec 	from requestSpec()
+ * @param {Object} info 	from summarizeInfo()
+ * @param {Object} convert 	the audio conversion settings
+ */
+function pickItags(spec, info, convert) {
+	const formatOf = (itag) => info.formats.find((f) => f.itag === itag);
+	const format = formatOf(spec.itag);
+	if (!format || (spec.audioItag && !formatOf(spec.audioItag))) {
+		const itags = spec.audioItag ? `${spec.itag}+${spec.audioItag}` : spec.itag;
+		throw new FormatError(`format ${itags} is not available`);
+	}
+	if (spec.audioItag) {
+		const audio = formatOf(spec.audioItag);
+		spec.qualityLabel = `${format.qualityLabel || ""} + ${audio.audioBitrate || ""}kbps`;
+		return;
+	}
+	spec.qualityLabel = format.qualityLabel || "";
+	if (!format.hasVideo && AUDIO_CODECS[convert.codec]) {
+		spec.convert = convert;
+		spec.container = AUDIO_CODECS[convert.codec].ext;
+	} else {
+		spec.container = format.container;
+	}
+}
+
+/**
+ * Fetches info and runs the download queue, independently of any window.
+ * Used by the main process and the command line.
+ *
+ * Emits the events in EVENTS of api.js.
+ */
+class DownloadService extends EventEmitter {
+	/**
+	 * @param {Object} opts
+	 * @param {Function} opts.getOptions 	returns the current app options
+	 * @param {String} opts.queueFile 	where the queue is saved, if anywhere
+	 * @param {History} opts.history 	where finished jobs are recorded, if anywhere
+	 * @param {Function} opts.askCollision 	called with a filename when the
+	 * 										collision policy is "ask"
+	 * @param {InfoCache} opts.infoCache 	where info is cached, if anywhere
+	 */
+	constructor({ getOptions, queueFile, history, askCollision, infoCache }) {
+		super();
+		this.getOptions = getOptions;
+		this.queueFile = queueFile;
+		this.history = history;
+		this.infoCache = infoCache;
+		this.shown = undefined; // the video open in the window, see open()
+		const getInfo = infoCache
+			? (url, fresh) => (fresh ? infoCache.getInfo(url, 0) : infoCache.getFormats(url))
+			: undefined;
+		this.queue = new DownloadQueue(
+			withRetry(
+				(job, progress, signal) =>
+					downloadJob(job, progress, signal, this.getOptions(), { askCollision, getInfo }),
+				() => this.getOptions().retry
+			),
+			getOptions().maxConcurrent
+		);
+
+		this.queue.on("change", () => {
+			this.save();
+			this.emit("queue", this.getQueue());
+		});
+		this.queue.on("progress", (job) => this.emit("progress", { ...job }));
+		this.queue.on("remove", (job) => {
+			// active jobs clean up after themselves once their stream is closed
+			if (job.state !== "active" && job.state !== "done") discardPartial(job);
+		});
+		this.queue.on("finish", (job) => this.record(job));
+	}
+
+	/**
+	 * Load the saved queue and start downloading
+	 */
+	start() {
+		if (!this.queueFile) return;
+		let jobs = [];
+		try {
+			jobs = JSON.parse(fs.readFileSync(this.queueFile, "utf8"));
+		} catch (err) {}
+		this.queue.restore(jobs);
+	}
+
+	save() {
+		if (!this.queueFile) return;
+		try {
+			fs.mkdirSync(path.dirname(this.queueFile), { recursive: true });
+			fs.writeFileSync(this.queueFile, JSON.stringify(this.queue.toJSON()));
+		} catch (err) {
+			console.error(err);
+		}
+	}
+
+	record(job) {
+		let size = 0;
+		try {
+			if (job.state === "done") size = fs.statSync(job.filename).size;
+		} catch (err) {}
+		const entry = historyRecord(job, size);
+		try {
+			if (this.history) this.history.append(entry);
+		} catch (err) {
+			console.error(err);
+		}
+		this.emit("record", entry);
+	}
+
+	/**
+	 * Info of a video, from the cache unless it is too old
+	 * @param {String} url
+	 * @param {Boolean} refresh 	fetch it even if it is cached
+	 */
+	async getInfo(url, refresh = false) {
+		const cache = this.infoCache;
+		let info;
+		if (!cache) info = await fetchInfo(url);
+		else info = await (refresh ? cache.getInfo(url, 0) : cache.getMetadata(url));
+		return summarizeInfo(info, url);
+	}
+
+	/**
+	 * @returns {Object} see stats() in info-cache.js, undefined without a cache
+	 */
+	cacheStats() {
+		return this.infoCache ? this.infoCache.stats() : undefined;
+	}
+
+	clearCache() {
+		if (this.infoCache) this.infoCache.clear();
+	}
+
+	getPlaylist(url) {
+		return getPlaylist(url);
+	}
+
+	/**
+	 * Info of a video, or a playlist for playlist URLs
+	 * @param {String} url
+	 * @param {Boolean} refresh 	see getInfo()
+	 * @returns {Object} { info } or { playlist }
+	 */
+	async lookup(url, refresh = false) {
+		if (isPlaylistUrl(url)) return { playlist: await this.getPlaylist(url) };
+		return { info: await this.getInfo(url, refresh) };
+	}
+
+	/**
+	 * Open a URL in the window, see lookup(). A video becomes the one
+	 * previewTemplate() and matchPresets() describe, which queuing from the
+	 * command line, the local API or subscriptions leaves alone.
+	 */
+	async open(url, refresh = false) {
+		const opened = await this.lookup(url, refresh);
+		if (opened.info) this.shown = opened.info;
+		return opened;
+	}
+
+	constants() {
+		return constants();
+	}
+
+	/**
+	 * Where the video shown in the window would be saved with a template
+	 * @param {String} template
+	 * @param {Object} sample 	{ playlist, chapter } to preview as part of one
+	 * @returns {Object} { error, preview }
+	 */
+	previewTemplate(template, sample = {}) {
+		const error = validateTemplate(template);
+		if (error) return { error };
+		const info = this.shown;
+		if (!info) return { preview: "Get the info of a video to see a preview" };
+		const options = this.getOptions();
+		const container = options.mergeContainer || "mkv";
+		const { video, audio } = info.bestPairs[container];
+		const formats = video && audio ? [video, audio] : info.formats.slice(0, 1);
+		const ext = video && audio ? container : extensionOf(formats[0]);
+		const values = templateValues(info, { formats, ext, ...sample });
+		const profile = options.sanitize || defaultProfile();
+		return { preview: sanitizePath(renderTemplate(template, values), profile) };
+	}
+
+	/**
+	 * Check a range typed for a clip, see parseClip() in clip.js
+	 * @returns {Object} { clip, label } or { error }
+	 */
+	parseClip(start, end, duration) {
+		const { clip, error } = parseClip(start, end, duration);
+		return clip ? { clip, label: clipLabel(clip) } : { error };
+	}
+
+	/**
+	 * What each preset picks for the video shown in the window
+	 * @param {Array} presets 	options.presets
+	 * @returns {Array} { name, label, error } by preset, where label is empty
+	 * 					if nothing matches
+	 */
+	matchPresets(presets) {
+		const formats = this.shown ? this.shown.formats : [];
+		return presets.map(({ name, rule }) => {
+			const picked = selectFormats(formats, rule);
+			if (!picked) return { name, label: "", error: "" };
+			if (picked.error) return { name, label: "", error: picked.error };
+			return { name, label: pickedLabel(picked), error: "" };
+		});
+	}
+
+	/**
+	 * Check a preset rule typed in Settings, see parseRule() in presets.js
+	 * @returns {Object} { error }
+	 */
+	checkRule(rule) {
+		return { error: parseRule(rule).error || "" };
+	}
+
+	/**
+	 * Check a proxy URL typed in Settings, see checkProxy() in proxy.js
+	 * @returns {Object} { error }
+	 */
+	checkProxy(url) {
+		return { error: checkProxy(`${url}`) };
+	}
+
+	getQueue() {
+		return this.queue.toJSON();
+	}
+
+	enqueue(specs) {
+		return specs.map((spec) => ({ ...this.queue.add(spec) }));
+	}
+
+	/**
+	 * Queue what a window asks for. Windows only pick the videos and their
+	 * formats, the save location, template and the rest come from the options.
+	 * @param {Array} intents 	see JobIntent in api.js
+	 * @returns {Promise<Array>} the queued jobs
+	 */
+	async queueJobs(intents) {
+		if (!Array.isArray(intents)) throw new RequestError("nothing to queue");
+		const options = this.getOptions();
+		const specs = [];
+		for (const intent of intents) specs.push(await this.intentSpec(options, intent));
+		return this.enqueue(specs);
+	}
+
+	/**
+	 * The job of a JobIntent, see queueJobs()
+	 * @param {Object} options 	the app options
+	 * @param {Object} intent
+	 */
+	async intentSpec(options, intent) {
+		if (!intent || typeof intent.url !== "string") throw new RequestError("url is required");
+		const request = intentRequest(intent);
+		const format = checkRequest(request);
+		const playlist = intentPlaylist(intent.playlist);
+		// playlist entries only use the playlist template when asked to
+		if (playlist && !intent.numbered) request.template = options.template;
+		const spec = requestSpec(
+			options,
+			{ ...request, format },
+			{ url: intent.url, title: `${intent.title || ""}`, playlist }
+		);
+		spec.subtitles = intentSubtitles(options, intent.subtitles);
+		if (!spec.itag) return spec;
+		const info = await this.getInfo(intent.url);
+		pickItags(spec, info, convertOf(options, request));
+		if (intent.clip) {
+			const duration = parseInt(info.videoDetails.lengthSeconds, 10) || 0;
+			const { clip, error } = parseClip(intent.clip.start, intent.clip.end, duration);
+			if (error) throw new RequestError(error);
+			if (clip) {
+				spec.clip = { ...clip, accurate: !!(options.clip && options.clip.accurate) };
+				spec.qualityLabel = `${spec.qualityLabel} ${clipLabel(clip)}`;
+			}
+		}
+		return spec;
+	}
+
+	/**
+	 * Queue the job of a history entry again
+	 * @param {Number} time 	when the entry was recorded
+	 * @param {String} url 	of the entry
+	 * @returns {Array} the queued job
+	 */
+	requeue(time, url) {
+		const entry = this.getHistory().find((e) => e.time === time && e.url === url);
+		if (!entry || !entry.job) throw new RequestError("the entry is not in the history");
+		return this.enqueue([entry.job]);
+	}
+
+	/**
+	 * Queue a video, or every video of a playlist, the way the app would
+	 * without anyone picking formats
+	 * @param {String} url
+	 * @param {Object} request 	{ format, preset, container, audioFormat,
+	 * 							saveDir, template, ignoreArchive } where format
+	 * 							is a key of PREFERENCES (the default is
+	 * 							"merged"), an itag or two itags joined by + to
+	 * 							merge, preset is the name of a preset used
+	 * 							instead, and the others default to the app
+	 * 							options
+	 * @returns {Promise<Array>} the queued jobs, rejects with a RequestError
+	 * 							if the request itself is invalid
+	 */
+	async queueUrl(url, request = {}) {
+		const format = checkRequest(request);
+		const options = this.getOptions();
+		const req = { ...request, format };
+
+		const { info, playlist } = await this.lookup(url);
+		if (playlist) {
+			if (!PREFERENCES[format] && !request.preset) {
+				throw new Error("playlists cannot be queued by itag");
+			}
+			const count = playlist.items.length;
+			const specs = playlist.items.map((item) =>
+				requestSpec(options, req, {
+					url: item.url,
+					title: item.title,
+					playlist: { title: playlist.title, index: item.index, count },
+				})
+			);
+			return this.enqueue(specs);
+		}
+		const spec = requestSpec(options, req, {
+			url: info.videoDetails.video_url,
+			title: info.videoDetails.title,
+		});
+		if (spec.preset) {
+			const picked = selectFormats(info.formats, spec.preset.rule);
+			if (!picked || picked.error) {
+				throw new FormatError(`no format matches preset ${spec.preset.name}`);
+			}
+		} else if (spec.itag) {
+			pickItags(spec, info, convertOf(options, req));
+		}
+		return this.enqueue([spec]);
+	}
+
+	/**
+	 * Queue every URL of a pasted list or an imported text or CSV file,
+	 * opening a few at a time
+	 * @param {String} text 	see parseBatch() in batch.js
+	 * @param {Object} request 	{ format, preset, ignoreArchive }, see queueUrl(),
+	 * 							for rows without their own preset. Rows are saved
+	 * 							in the save location of the app, or in folders
+	 * 							they name.
+	 * @returns {Promise<Array>} { line, url, queued, error } for every row,
+	 * 							where queued is the number of jobs added
+	 */
+	async queueBatch(text, request = {}) {
+		const options = this.getOptions();
+		const { concurrency } = { ...DEFAULT_BATCH, ...options.batch };
+		const base = {
+			format: request.format,
+			preset: request.preset,
+			ignoreArchive: request.ignoreArchive === true,
+			saveDir: options.saveDir,
+		};
+		return mapLimit(parseBatch(`${text}`), concurrency, async (row) => {
+			const result = { line: row.line, url: row.url, queued: 0, error: row.error || "" };
+			if (row.error) return result;
+			try {
+				result.queued = (await this.queueUrl(row.url, rowRequest(base, row))).length;
+			} catch (err) {
+				result.error = `${err.message || err}`;
+			}
+			return result;
+		});
+	}
+
+	pause(id) {
+		this.queue.pause(id);
+	}
+
+	resume(id) {
+		this.queue.resume(id);
+	}
+
+	remove(id) {
+		this.queue.remove(id);
+	}
+
+	clearDone() {
+		this.queue.clearDone();
+	}
+
+	setMaxConcurrent(n) {
+		this.queue.setMaxConcurrent(n);
+	}
+
+	getHistory() {
+		return this.history ? this.history.load() : [];
+	}
+
+	/**
+	 * History entries matching a search, newest first, see searchHistory()
+	 * in history.js
+	 * @returns {Object} { entries, total } with at most `limit` entries, each
+	 * 					with `exists` set if its file is still there
+	 */
+	searchHistory(query, outcome, limit = 200) {
+		const found = searchHistory(this.getHistory(), query, outcome);
+		const entries = found
+			.slice(0, limit)
+			.map((e) => ({ ...e, exists: e.outcome === "done" && fs.existsSync(e.path) }));
+		return { entries, total: found.length };
+	}
+
+	clearHistory() {
+		if (this.history) this.history.clear();
+		this.emit("history", []);
+	}
+
+	archivedIds() {
+		const archive = this.getOptions().archive;
+		if (!archive || !archive.enabled || !archive.file) return [];
+		return [...new Archive(archive.file).ids()];
+	}
+}
+
+module.exports = { summarizeInfo, DownloadService };
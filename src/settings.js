@@ -0,0 +1,797 @@
+import { html, render } from "../node_modules/lit-html/lit-html.js";
+
+import { constants } from "./constants.js";
+
+const drafts = {}; // templates being edited, by option key
+const previews = {}; // option key => { key, error, preview } last checked by the service
+const ruleErrors = new Map(); // preset index => error of the rule typed there
+let proxyError = ""; // why the last proxy URL typed was not saved
+let proxyState; // { password, encrypted } of the proxy password kept by the main process
+let cookieState; // { count, saved, encrypted, error } of the cookies sent to YouTube
+let pastedCookies = ""; // Cookie header or cookies.txt typed into the cookies field
+let cacheStats; // { key, stats } of the info cache, loaded again when the video changes
+
+/**
+ * Validation error and preview of a template, as last checked by the download
+ * service. Checks again, and renders the settings once done, when the
+ * template, the loaded video or the filename settings changed.
+ * @param {String} key 	option key of the template
+ * @param {String} template
+ * @param {Object} options 	the app options
+ * @param {Object} info 	the loaded video, if any
+ * @param {Object} sample 	{ playlist, chapter } to preview as part of one
+ * @param {Function} rerender 	renders the settings again
+ */
+function checkTemplate(key, template, options, info, sample, rerender) {
+	const checkKey = JSON.stringify([
+		template,
+		info && info.videoDetails.videoId,
+		options.sanitize,
+		options.mergeContainer,
+	]);
+	const last = previews[key];
+	if (!last || last.key !== checkKey) {
+		previews[key] = { key: checkKey, ...last };
+		window.ytdl.previewTemplate(template, sample).then((result) => {
+			if (previews[key].key !== checkKey) return;
+			previews[key] = { key: checkKey, error: "", preview: "", ...result };
+			rerender();
+		});
+	}
+	return previews[key];
+}
+
+/**
+ * Location of external tools
+ * @param {Object} options 	the app options
+ * @param {Function} setOption 	called with (key, value) to persist an option
+ */
+const toolsSection = (options, setOption) => html`<fieldset>
+	<legend>Tools</legend>
+	<label>
+		ffmpeg
+		<input
+			type="text"
+			class="path"
+			readonly
+			placeholder="ffmpeg (search PATH)"
+			.value=${options.ffmpegPath || ""}
+		/>
+	</label>
+	<button @click=${() => window.ytdl.chooseFfmpeg()}>Browse</button>
+	<button ?disabled=${!options.ffmpegPath} @click=${() => setOption("ffmpegPath", "")}>
+		Search PATH
+	</button>
+</fieldset>`;
+
+/**
+ * Filename templates with validation and a live preview
+ * @param {Object} options 	the app options
+ * @param {Function} setOption 	called with (key, value) to persist an option
+ * @param {Object} info 	result of ytdl.getInfo(), if a video is loaded
+ * @param {Function} rerender 	renders the settings again
+ */
+const templateSection = (options, setOption, info, rerender) => {
+	const field = (key, label, fallback, sample) => {
+		const value = key in drafts ? drafts[key] : options[key] || fallback;
+		const { error, preview } = checkTemplate(key, value, options, info, sample, rerender);
+		return html`<div>
+			<label>
+				${label}
+				<input
+					type="text"
+					class="path"
+					.value=${value}
+					@input=${(e) => {
+						drafts[key] = e.target.value;
+						rerender();
+					}}
+					@change=${async (e) => {
+						const template = e.target.value;
+						const result = await window.ytdl.previewTemplate(template, sample);
+						if (result.error || drafts[key] !== template) return;
+						delete drafts[key];
+						setOption(key, template);
+					}}
+				/>
+			</label>
+			<button
+				@click=${() => {
+					delete drafts[key];
+					setOption(key, fallback);
+				}}
+			>
+				Reset
+			</button>
+			<div class=${error ? "error" : "preview"}>
+				${error || preview || ""}
+			</div>
+		</div>`;
+	};
+
+	return html`<fieldset>
+		<legend>Filenames</legend>
+		${field("template", "Template", constants.defaults.template, {})}
+		${field("playlistTemplate", "Playlist template", constants.defaults.playlistTemplate, {
+			playlist: { title: "Playlist", index: 1, count: 25 },
+		})}
+		${field("chapterTemplate", "Chapter template", constants.defaults.chapterTemplate, {
+			chapter: { title: "Introduction", index: 1, count: 12 },
+		})}
+		<label>
+			Make filenames safe for
+			<select @change=${(e) => setOption("sanitize", e.target.value)}>
+				${Object.entries(constants.profiles).map(
+					([key, label]) => html`<option
+						value=${key}
+						.selected=${key === (options.sanitize || constants.defaults.sanitize)}
+					>
+						${label}
+					</option>`
+				)}
+			</select>
+		</label>
+		<label>
+			When the file exists
+			<select @change=${(e) => setOption("collision", e.target.value)}>
+				${Object.entries(constants.collisions).map(
+					([key, label]) => html`<option
+						value=${key}
+						.selected=${key === (options.collision || "number")}
+					>
+						${label}
+					</option>`
+				)}
+			</select>
+		</label>
+		<details>
+			<summary>Fields</summary>
+			<p>
+				Use <code>{field}</code>, <code>{field|other|fallback text}</code>,
+				<code>{field:03}</code> for zero padding and <code>/</code> for folders.
+			</p>
+			<table>
+				${Object.entries(constants.fields).map(
+					([name, description]) =>
+						html`<tr>
+							<td><code>{${name}}</code></td>
+							<td>${description}</td>
+						</tr>`
+				)}
+			</table>
+		</details>
+	</fieldset>`;
+};
+
+/**
+ * Default conversion of audio-only downloads
+ * @param {Object} options 	the app options
+ * @param {Function} setOption 	called with (key, value) to persist an option
+ */
+const convertSection = (options, setOption) => {
+	const convert = { ...constants.defaults.convert, ...options.convert };
+	const set = (key, value) => setOption("convert", { ...convert, [key]: value });
+	const codec = constants.audioCodecs[convert.codec];
+
+	return html`<fieldset>
+		<legend>Audio conversion</legend>
+		<label>
+			Convert audio-only downloads to
+			<select @change=${(e) => set("codec", e.target.value)}>
+				<option value="original" .selected=${convert.codec === "original"}>
+					Original (no conversion)
+				</option>
+				${Object.entries(constants.audioCodecs).map(
+					([key, c]) => html`<option value=${key} .selected=${convert.codec === key}>
+						${c.label}
+					</option>`
+				)}
+			</select>
+		</label>
+		${codec && codec.quality === "vbr"
+			? html`<label>
+					VBR quality (0 best - 9 smallest)
+					<input
+						type="number"
+						min="0"
+						max="9"
+						.value=${`${convert.mp3Quality}`}
+						@change=${(e) =>
+							set("mp3Quality", Math.min(9, Math.max(0, parseInt(e.target.value, 10) || 0)))}
+					/>
+			  </label>`
+			: ""}
+		${codec && codec.quality === "bitrate"
+			? html`<label>
+					Bitrate (kbps)
+					<input
+						type="number"
+						min="32"
+						max="512"
+						step="16"
+						.value=${`${convert.bitrate}`}
+						@change=${(e) => set("bitrate", parseInt(e.target.value, 10) || 160)}
+					/>
+			  </label>`
+			: ""}
+	</fieldset>`;
+};
+
+/**
+ * What gets written into downloaded files
+ * @param {Object} options 	the app options
+ * @param {Function} setOption 	called with (key, value) to persist an option
+ */
+const embedSection = (options, setOption) => {
+	const embed = { ...constants.defaults.embed, ...options.embed };
+	const checkbox = (key, label) => html`<label>
+		<input
+			type="checkbox"
+			.checked=${embed[key]}
+			@change=${(e) => setOption("embed", { ...embed, [key]: e.target.checked })}
+		/>
+		${label}
+	</label>`;
+
+	return html`<fieldset>
+		<legend>Embedding (needs ffmpeg)</legend>
+		${checkbox("metadata", "Title, channel, date and description")}
+		${checkbox("thumbnail", "Thumbnail as cover art")}
+	</fieldset>`;
+};
+
+/**
+ * What to do with the chapters of a video
+ * @param {Object} options 	the app options
+ * @param {Function} setOption 	called with (key, value) to persist an option
+ */
+const chaptersSection = (options, setOption) => {
+	const chapters = { ...constants.defaults.chapters, ...options.chapters };
+	return html`<fieldset>
+		<legend>Chapters (needs ffmpeg)</legend>
+		<label>
+			Chapters from the player or the description
+			<select @change=${(e) => setOption("chapters", { ...chapters, mode: e.target.value })}>
+				${Object.entries(constants.chapterModes).map(
+					([key, label]) => html`<option value=${key} .selected=${key === chapters.mode}>
+						${label}
+					</option>`
+				)}
+			</select>
+		</label>
+		${chapters.mode === "split"
+			? html`<div>Chapter files are named by the chapter template, the full video is kept.</div>`
+			: ""}
+	</fieldset>`;
+};
+
+/**
+ * Download archive shared with yt-dlp
+ * @param {Object} options 	the app options
+ * @param {Function} setOption 	called with (key, value) to persist an option
+ */
+const archiveSection = (options, setOption) => {
+	const archive = { ...constants.defaults.archive, ...options.archive };
+	return html`<fieldset>
+		<legend>Download archive</legend>
+		<label>
+			<input
+				type="checkbox"
+				.checked=${archive.enabled}
+				@change=${(e) => setOption("archive", { ...archive, enabled: e.target.checked })}
+			/>
+			Skip videos in the archive and add finished ones
+		</label>
+		<div>
+			<label>
+				File
+				<input type="text" class="path" readonly .value=${archive.file} />
+			</label>
+			<button @click=${() => window.ytdl.chooseArchive()}>Browse</button>
+			<button @click=${() => window.ytdl.importArchive()}>Import</button>
+			<button @click=${() => window.ytdl.exportArchive()}>Export</button>
+		</div>
+	</fieldset>`;
+};
+
+/**
+ * Info cache lifetimes and statistics
+ */
+const cacheSection = (options, setOption, info, rerender) => {
+	const cache = { ...constants.defaults.infoCache, ...options.infoCache };
+	const set = (key, value) => setOption("infoCache", { ...cache, [key]: value });
+	const key = info ? info.videoDetails.videoId : "";
+	const load = () => {
+		cacheStats = { key };
+		window.ytdl.cacheStats().then((stats) => {
+			if (cacheStats.key !== key) return;
+			cacheStats = { key, stats };
+			rerender();
+		});
+	};
+	if (!cacheStats || cacheStats.key !== key) load();
+	const stats = cacheStats.stats;
+	const mb = (size) => `${(size / 1048576).toFixed(1)} MB`;
+
+	return html`<fieldset>
+		<legend>Info cache</legend>
+		<label>
+			<input
+				type="checkbox"
+				.checked=${cache.enabled}
+				@change=${(e) => set("enabled", e.target.checked)}
+			/>
+			Reuse the info of videos opened before
+		</label>
+		<div>
+			<label>
+				Show for
+				<input
+					type="number"
+					min="0"
+					.value=${`${cache.metadataDays}`}
+					@change=${(e) => set("metadataDays", Math.max(0, parseFloat(e.target.value) || 0))}
+				/>
+				days
+			</label>
+			<label>
+				Download with for
+				<input
+					type="number"
+					min="0"
+					max="300"
+					.value=${`${cache.formatMinutes}`}
+					@change=${(e) =>
+						set("formatMinutes", Math.min(300, Math.max(0, parseFloat(e.target.value) || 0)))}
+				/>
+				minutes
+			</label>
+		</div>
+		<div>
+			${stats
+				? `${stats.entries} videos, ${mb(stats.size)}` +
+				  (stats.oldest ? `, oldest from ${new Date(stats.oldest).toLocaleDateString()}` : "") +
+				  `. ${stats.hits} hits and ${stats.misses} misses since the app started.`
+				: ""}
+			<button @click=${load}>Update</button>
+			<button @click=${() => window.ytdl.clearCache().then(load)}>Clear</button>
+		</div>
+	</fieldset>`;
+};
+
+/**
+ * Named rules picking formats, and the preset used by default
+ * @param {Object} options 	the app options
+ * @param {Function} setOption 	called with (key, value) to persist an option
+ * @param {Function} rerender 	renders the settings again
+ */
+const presetsSection = (options, setOption, rerender) => {
+	const presets = options.presets || [];
+	const settings = { ...constants.defaults.defaultPreset, ...options.defaultPreset };
+	const links = { ...constants.defaults.links, ...options.links };
+	const save = (list) => {
+		ruleErrors.clear();
+		setOption("presets", list);
+	};
+	const change = (i, key, value) =>
+		setOption(
+			"presets",
+			presets.map((p, j) => (j === i ? { ...p, [key]: value } : p))
+		);
+	const add = () => {
+		const preset = { name: `Preset ${presets.length + 1}`, rule: "any", container: "" };
+		save([...presets, preset]);
+	};
+	const rename = (i, name) => {
+		if (!name || presets.some((p, j) => j !== i && p.name === name)) return rerender();
+		if (settings.name === presets[i].name) setOption("defaultPreset", { ...settings, name });
+		change(i, "name", name);
+		if (links.preset === presets[i].name) setOption("links", { ...links, preset: name });
+	};
+	const remove = (i) => {
+		if (links.preset === presets[i].name) setOption("links", { ...links, preset: "" });
+		save(presets.filter((x, j) => j !== i));
+	};
+	const checkRule = async (i, rule) => {
+		const { error } = await window.ytdl.checkRule(rule);
+		if (error) ruleErrors.set(i, error);
+		else ruleErrors.delete(i);
+		if (error) rerender();
+		else change(i, "rule", rule);
+	};
+
+	return html`<fieldset>
+		<legend>Presets</legend>
+		<table class="presets">
+			${presets.map(
+				(p, i) => html`<tr>
+					<td>
+						<input
+							type="text"
+							.value=${p.name}
+							@change=${(e) => rename(i, e.target.value.trim())}
+						/>
+					</td>
+					<td>
+						<input
+							type="text"
+							class=${ruleErrors.has(i) ? "rule invalid" : "rule"}
+							title=${ruleErrors.get(i) || ""}
+							.value=${p.rule}
+							@change=${(e) => checkRule(i, e.target.value)}
+						/>
+					</td>
+					<td>
+						<select
+							title="Container to merge into"
+							@change=${(e) => change(i, "container", e.target.value)}
+						>
+							<option value="" .selected=${!p.container}>Merge setting</option>
+							${constants.containers.map(
+								(c) => html`<option .selected=${c === p.container}>${c}</option>`
+							)}
+						</select>
+					</td>
+					<td>
+						<button @click=${() => remove(i)}>Remove</button>
+					</td>
+				</tr>`
+			)}
+		</table>
+		${[...ruleErrors].map(
+			([i, error]) => html`<div class="error">${presets[i].name}: ${error}</div>`
+		)}
+		<button @click=${add}>Add preset</button>
+		<button @click=${() => save(constants.defaults.presets)}>Restore defaults</button>
+		<div>
+			<label>
+				Default
+				<select @change=${(e) => setOption("defaultPreset", { ...settings, name: e.target.value })}>
+					<option value="" .selected=${!settings.name}>None</option>
+					${presets.map(
+						(p) => html`<option value=${p.name} .selected=${p.name === settings.name}>
+							${p.name}
+						</option>`
+					)}
+				</select>
+			</label>
+			<label>
+				<input
+					type="checkbox"
+					.checked=${settings.auto}
+					@change=${(e) => setOption("defaultPreset", { ...settings, auto: e.target.checked })}
+				/>
+				Download with it right after Get Info
+			</label>
+		</div>
+		<div class="preview">
+			Rules are video, audio, av (both) or any, then filters like [height<=1080],
+			[container=mp4] or [codecs^=avc1] and an order like (-fps, +size) where - prefers
+			high values. Join video + audio to merge and separate fallbacks with /. Fields:
+			${constants.presetFields.join(", ")}
+		</div>
+	</fieldset>`;
+};
+
+/**
+ * What to do with ytdl-desktop:// links and URLs the app is launched with
+ * @param {Object} options 	the app options
+ * @param {Function} setOption 	called with (key, value) to persist an option
+ */
+const linksSection = (options, setOption) => {
+	const links = { ...constants.defaults.links, ...options.links };
+	const set = (key, value) => setOption("links", { ...links, [key]: value });
+	return html`<fieldset>
+		<legend>Opened links</legend>
+		<label>
+			<input
+				type="checkbox"
+				.checked=${links.getInfo}
+				@change=${(e) => set("getInfo", e.target.checked)}
+			/>
+			Get info
+		</label>
+		<label>
+			Then queue
+			<select @change=${(e) => set("preset", e.target.value)}>
+				<option value="" .selected=${!links.preset}>Nothing</option>
+				${Object.entries(constants.preferences).map(
+					([key, label]) => html`<option value=${key} .selected=${links.preset === key}>
+						${label}
+					</option>`
+				)}
+				${(options.presets || []).map(
+					(p) => html`<option value=${p.name} .selected=${links.preset === p.name}>
+						${p.name}
+					</option>`
+				)}
+			</select>
+		</label>
+		<label>
+			<input
+				type="checkbox"
+				.checked=${links.autoQueue}
+				@change=${(e) => set("autoQueue", e.target.checked)}
+			/>
+			Queue without asking
+		</label>
+	</fieldset>`;
+};
+
+/**
+ * Settings of the localhost API for browser extensions and scripts
+ * @param {Object} options 	the app options
+ * @param {Function} setOption 	called with (key, value) to persist an option
+ */
+const rpcSection = (options, setOption) => {
+	const rpc = { ...constants.defaults.rpc, ...options.rpc };
+	const set = (key, value) => setOption("rpc", { ...rpc, [key]: value });
+	return html`<fieldset>
+		<legend>Local API</legend>
+		<label>
+			<input
+				type="checkbox"
+				.checked=${rpc.enabled}
+				@change=${(e) => set("enabled", e.target.checked)}
+			/>
+			Accept JSON-RPC calls on http://127.0.0.1:${rpc.port}/rpc
+		</label>
+		<div>
+			<label>
+				Port
+				<input
+					type="number"
+					min="1024"
+					max="65535"
+					.value=${`${rpc.port}`}
+					@change=${(e) => {
+						const port = parseInt(e.target.value, 10);
+						if (port >= 1024 && port <= 65535) set("port", port);
+					}}
+				/>
+			</label>
+			<button @click=${() => window.ytdl.copyRpcToken()}>Copy token</button>
+			<button @click=${() => window.ytdl.newRpcToken()}>New token</button>
+		</div>
+	</fieldset>`;
+};
+
+/**
+ * Proxy the app connects through, and more to switch to when rate limited
+ * @param {Object} options 	the app options
+ * @param {Function} setOption 	called with (key, value) to persist an option
+ * @param {Function} rerender 	renders the settings again
+ */
+const proxySection = (options, setOption, rerender) => {
+	const proxy = { ...constants.defaults.proxy, ...options.proxy };
+	const set = (key, value) => setOption("proxy", { ...proxy, [key]: value });
+	const setProxies = async (key, list) => {
+		const checked = await Promise.all(list.map((url) => window.ytdl.checkProxy(url)));
+		const problems = checked.map((c) => c.error).filter((e) => e);
+		proxyError = problems[0] || "";
+		if (proxyError) rerender();
+		else set(key, key === "url" ? list[0] || "" : list);
+	};
+	const showPassword = (promise) =>
+		promise.then((state) => {
+			proxyState = state;
+			rerender();
+		});
+	if (!proxyState) {
+		proxyState = {};
+		showPassword(window.ytdl.proxyStatus());
+	}
+	const text = (key, label) => html`<label>
+		${label}
+		<input
+			type="text"
+			.value=${proxy[key]}
+			@change=${(e) => set(key, e.target.value.trim())}
+		/>
+	</label>`;
+
+	return html`<fieldset>
+		<legend>Proxy</legend>
+		<label>
+			<input
+				type="checkbox"
+				.checked=${proxy.enabled}
+				@change=${(e) => set("enabled", e.target.checked)}
+			/>
+			Connect through a proxy
+		</label>
+		<div>
+			<label>
+				URL
+				<input
+					type="text"
+					class="path"
+					placeholder="http://proxy:8080 or socks5://host:1080"
+					.value=${proxy.url}
+					@change=${(e) => setProxies("url", [e.target.value.trim()].filter((u) => u))}
+				/>
+			</label>
+		</div>
+		<div>
+			${text("username", "User")}
+			<label>
+				Password
+				<input
+					type="password"
+					placeholder=${proxyState.password ? "saved" : ""}
+					@change=${(e) => {
+						showPassword(window.ytdl.setProxyPassword(e.target.value));
+						e.target.value = "";
+					}}
+				/>
+			</label>
+			<button
+				?disabled=${!proxyState.password}
+				@click=${() => showPassword(window.ytdl.setProxyPassword(""))}
+			>
+				Forget password
+			</button>
+		</div>
+		${proxyState.password && proxyState.encrypted === false
+			? html`<div>Without a keyring, the password is kept until the app quits.</div>`
+			: ""}
+		<div>
+			<label>
+				No proxy for
+				<input
+					type="text"
+					class="path"
+					.value=${proxy.noProxy}
+					@change=${(e) => set("noProxy", e.target.value.trim())}
+				/>
+			</label>
+		</div>
+		<div>
+			<label>
+				More proxies, one per line
+				<textarea
+					rows="3"
+					.value=${proxy.pool.join("\n")}
+					@change=${(e) =>
+						setProxies(
+							"pool",
+							e.target.value
+								.split("\n")
+								.map((l) => l.trim())
+								.filter((l) => l)
+						)}
+				></textarea>
+			</label>
+			<label>
+				<input
+					type="checkbox"
+					.checked=${proxy.rotate}
+					@change=${(e) => set("rotate", e.target.checked)}
+				/>
+				Switch to the next one on 429 Too Many Requests
+			</label>
+		</div>
+		${proxyError ? html`<div class="error">${proxyError}</div>` : ""}
+	</fieldset>`;
+};
+
+/**
+ * Cookies for age-restricted and members-only videos
+ * @param {Function} rerender 	renders the settings again
+ */
+const cookiesSection = (rerender) => {
+	const api = window.ytdl;
+	const show = (promise) =>
+		promise.then((state) => {
+			cookieState = state;
+			rerender();
+		});
+	if (!cookieState) {
+		cookieState = {};
+		show(api.cookieStatus());
+	}
+	const { count, saved, encrypted, error } = cookieState;
+	const status = !count
+		? "No cookies are sent to YouTube."
+		: `${count} cookies are sent to YouTube, ` +
+		  (saved ? "saved encrypted." : "kept until the app quits as there is no keyring to encrypt them.");
+
+	return html`<fieldset>
+		<legend>Cookies</legend>
+		<div>${count === undefined ? "" : status}</div>
+		<label>
+			Paste a Cookie header or the content of a cookies.txt file
+			<textarea
+				rows="3"
+				.value=${pastedCookies}
+				@input=${(e) => (pastedCookies = e.target.value)}
+			></textarea>
+		</label>
+		<div>
+			<button
+				@click=${() =>
+					show(api.setCookies(pastedCookies)).then(() => {
+						if (!cookieState.error) pastedCookies = "";
+						rerender();
+					})}
+			>
+				Use these cookies
+			</button>
+			<button @click=${() => show(api.importCookies())}>Import cookies.txt</button>
+			<button ?disabled=${!count} @click=${() => show(api.clearCookies())}>Forget cookies</button>
+		</div>
+		${error ? html`<div class="error">${error}</div>` : ""}
+		${encrypted === false
+			? html`<div>Without a keyring, cookies are not saved and have to be imported again.</div>`
+			: ""}
+	</fieldset>`;
+};
+
+/**
+ * Settings of the retry policy
+ * @param {Object} options 	the app options
+ * @param {Function} setOption 	called with (key, value) to persist an option
+ */
+const retrySection = (options, setOption) => {
+	const retry = { ...constants.defaults.retry, ...options.retry };
+	const set = (key, value) => setOption("retry", { ...retry, [key]: value });
+	const number = (key, label, step = 1) => html`<label>
+		${label}
+		<input
+			type="number"
+			min="0"
+			step=${step}
+			.value=${`${retry[key]}`}
+			@change=${(e) => set(key, Math.max(0, parseFloat(e.target.value) || 0))}
+		/>
+	</label>`;
+
+	return html`<fieldset>
+		<legend>Retries</legend>
+		${number("maxAttempts", "Max attempts")}
+		${number("baseDelay", "First delay (s)", 0.5)}
+		${number("maxDelay", "Max delay (s)")}
+		${number("jitter", "Jitter", 0.1)}
+		<div>
+			Retry on:
+			${Object.entries(constants.errorClasses).map(
+				([key, label]) => html`<label>
+					<input
+						type="checkbox"
+						.checked=${!!retry.retryOn[key]}
+						@change=${(e) =>
+							set("retryOn", { ...retry.retryOn, [key]: e.target.checked })}
+					/>
+					${label}
+				</label>`
+			)}
+		</div>
+	</fieldset>`;
+};
+
+/**
+ * Render the settings panel
+ * @param {Element} container
+ * @param {Object} options 	the app options
+ * @param {Function} setOption 	called with (key, value) to persist an option
+ * @param {Object} info 	result of ytdl.getInfo(), if a video is loaded
+ */
+export function renderSettings(container, options, setOption, info) {
+	const rerender = () => renderSettings(container, options, setOption, info);
+	const update = (key, value) => {
+		setOption(key, value);
+		rerender();
+	};
+	render(
+		html`${toolsSection(options, update)}
+		${templateSection(options, update, info, rerender)} ${convertSection(options, update)}
+		${embedSection(options, update)} ${chaptersSection(options, update)}
+		${presetsSection(options, update, rerender)} ${archiveSection(options, update)}
+		${cacheSection(options, update, info, rerender)}
+		${linksSection(options, update)}
+		${rpcSection(options, update)}
+		${proxySection(options, update, rerender)} ${cookiesSection(rerender)}
+		${retrySection(options, update)}`,
+		container
+	);
+}
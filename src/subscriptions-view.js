@@ -0,0 +1,160 @@
+import { html, render } from "../node_modules/lit-html/lit-html.js";
+import { constants } from "./constants.js";
+
+let channel = ""; // channel typed in the add field
+let status = ""; // result of the last add or check
+
+/**
+ * Render the followed channels
+ * @param {Element} container
+ * @param {Array} subscriptions 	from the main process, see subscriptions.js
+ * @param {Object} opts
+ * @param {Object} opts.options 	the app options
+ * @param {Function} opts.setOption 	called with (key, value) to persist an option
+ * @param {Function} opts.serviceError 	message of an error thrown by the service
+ */
+export function renderSubscriptions(container, subscriptions, opts) {
+	const { options, setOption, serviceError } = opts;
+	const update = () => renderSubscriptions(container, subscriptions, opts);
+	const api = window.ytdl;
+	const polling = { ...constants.defaults.subscriptions, ...options.subscriptions };
+	const presets = options.presets || [];
+	const report = (promise, done) =>
+		promise
+			.then((result) => (status = done(result)))
+			.catch((err) => (status = serviceError(err)))
+			.then(update);
+	const add = () =>
+		report(api.addSubscription(channel), () => {
+			channel = "";
+			return "Subscribed, uploads from now on will be queued";
+		});
+	const check = (channelId) =>
+		report(
+			api.checkSubscriptions(channelId),
+			(n) => `${n || "No"} new video${n === 1 ? "" : "s"} queued`
+		);
+
+	render(
+		html`<div class="merge-row">
+				<input
+					type="text"
+					class="channel"
+					placeholder="Channel URL, @handle or id"
+					.value=${channel}
+					@input=${(e) => (channel = e.target.value)}
+					@keydown=${(e) => e.key === "Enter" && add()}
+				/>
+				<button @click=${add}>Subscribe</button>
+			</div>
+			<div class="merge-row">
+				<label>
+					<input
+						type="checkbox"
+						.checked=${polling.enabled}
+						@change=${(e) => setOption("subscriptions", { ...polling, enabled: e.target.checked })}
+					/>
+					Check every
+				</label>
+				<input
+					type="number"
+					min="5"
+					.value=${`${polling.interval}`}
+					@change=${(e) =>
+						setOption("subscriptions", {
+							...polling,
+							interval: Math.max(5, parseInt(e.target.value, 10) || polling.interval),
+						})}
+				/>
+				minutes while the app runs
+				<button @click=${() => check()}>Check now</button>
+			</div>
+			${status ? html`<div class="merge-row">${status}</div>` : ""}
+			${subscriptions.length
+				? html`<table>
+						<tbody>
+							<tr>
+								<th>Channel</th>
+								<th>Preset</th>
+								<th>Save location</th>
+								<th>Template</th>
+								<th>Checked</th>
+								<th></th>
+							</tr>
+							${subscriptions.map((s) => {
+								// the list is sent again once a change is saved
+								const failed = (err) => {
+									status = serviceError(err);
+									update();
+								};
+								const set = (key, value) =>
+									api.updateSubscription(s.channelId, { [key]: value }).catch(failed);
+								return html`<tr>
+									<td class="title">
+										<label title=${s.channelId}>
+											<input
+												type="checkbox"
+												title="Check this channel"
+												.checked=${s.enabled}
+												@change=${(e) => set("enabled", e.target.checked)}
+											/>
+											${s.title}
+										</label>
+									</td>
+									<td>
+										<select @change=${(e) => set("preset", e.target.value)}>
+											<option value="" .selected=${!s.preset}>
+												${constants.preferences.merged}
+											</option>
+											${presets.map(
+												(p) => html`<option value=${p.name} .selected=${p.name === s.preset}>
+													${p.name}
+												</option>`
+											)}
+										</select>
+									</td>
+									<td>
+										<input
+											type="text"
+											class="path"
+											readonly
+											placeholder=${options.saveDir || ""}
+											.value=${s.saveDir}
+										/>
+										<button
+											@click=${() => api.chooseSubscriptionDir(s.channelId).catch(failed)}
+										>
+											Browse
+										</button>
+										<button ?disabled=${!s.saveDir} @click=${() => set("saveDir", "")}>
+											Default
+										</button>
+									</td>
+									<td>
+										<input
+											type="text"
+											placeholder=${options.template || constants.defaults.template}
+											.value=${s.template}
+											@change=${(e) => set("template", e.target.value.trim())}
+										/>
+									</td>
+									<td title=${s.error}>
+										${s.lastChecked ? new Date(s.lastChecked).toLocaleString() : "never"}
+										${s.error ? "⚠" : ""}
+									</td>
+									<td>
+										<button @click=${() => check(s.channelId)}>Check</button>
+										<button
+											@click=${() => api.removeSubscription(s.channelId).catch(failed)}
+										>
+											Unsubscribe
+										</button>
+									</td>
+								</tr>`;
+							})}
+						</tbody>
+				  </table>`
+				: ""}`,
+		container
+	);
+}
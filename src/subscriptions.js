@@ -0,0 +1,285 @@
+const EventEmitter = require("events");
+const fs = require("fs");
+const path = require("path");
+const { getText } = require("./http.js");
+const { decodeEntities } = require("./subtitles.js");
+const { validateTemplate } = require("./template.js");
+const { findPreset } = require("./presets.js");
+const { FormatError } = require("./api.js");
+
+/**
+ * Polling settings, stored as options.subscriptions. `interval` is in
+ * minutes.
+ */
+const DEFAULT_SUBSCRIPTIONS = { enabled: true, interval: 60 };
+
+const CHANNEL_ID = /^UC[\w-]{22}$/;
+const VIDEO_ID = /(?:[?&]v=|youtu\.be\/|\/shorts\/)([\w-]{11})/;
+const MAX_FAILED = 50;
+
+const feedUrl = (channelId) =>
+	`https://www.youtube.com/feeds/videos.xml?channel_id=${encodeURIComponent(channelId)}`;
+
+/**
+ * Find the channel id of a channel URL, @handle or id
+ * @param {String} input
+ * @returns {Promise<String>}
+ */
+async function resolveChannel(input) {
+	const text = `${input || ""}`.trim();
+	if (CHANNEL_ID.test(text)) return text;
+	const direct = /youtube\.com\/channel\/(UC[\w-]{22})/.exec(text);
+	if (direct) return direct[1];
+
+	let url = text;
+	if (text.startsWith("@")) url = `https://www.youtube.com/${text}`;
+	else if (!/^https?:\/\//i.test(text)) throw new Error("not a channel URL, @handle or id");
+	if (!/^https:\/\/(www\.|m\.)?youtube\.com\//i.test(url)) throw new Error("not a YouTube URL");
+	const page = await getText(url);
+	const canonical = /<link rel="canonical" href="[^"]*\/channel\/(UC[\w-]{22})"/;
+	const found = canonical.exec(page) || /"externalId":"(UC[\w-]{22})"/.exec(page);
+	if (!found) throw new Error("no channel found at this URL");
+	return found[1];
+}
+
+/**
+ * Parse the Atom feed of a channel
+ * @param {String} xml
+ * @returns {Object} { title, videos } where videos are { videoId, title,
+ * 					url, published } newest first
+ */
+function parseFeed(xml) {
+	const tag = (text, name) => {
+		const m = new RegExp(`<${name}>([^<]*)</${name}>`).exec(text);
+		return m ? decodeEntities(m[1]) : "";
+	};
+	const head = xml.split("<entry>")[0];
+	const videos = [];
+	for (const m of xml.matchAll(/<entry>([\s\S]*?)<\/entry>/g)) {
+		const videoId = tag(m[1], "yt:videoId");
+		if (!videoId) continue;
+		videos.push({
+			videoId,
+			title: tag(m[1], "title"),
+			url: `https://www.youtube.com/watch?v=${videoId}`,
+			published: Date.parse(tag(m[1], "published")) || 0,
+		});
+	}
+	return { title: tag(head, "title"), videos };
+}
+
+/**
+ * Check settings of a subscription before storing them
+ * @param {Object} settings 	any of { preset, saveDir, template, enabled }
+ * @param {Array} presets 	the presets of the options
+ * @returns {Object} the settings
+ * @throws if a setting has the wrong type, the preset is unknown, the save
+ * 			location is relative or the template is invalid
+ */
+function checkSettings(settings, presets) {
+	if (!settings || typeof settings !== "object") throw new Error("invalid settings");
+	for (const key of ["preset", "saveDir", "template"]) {
+		if (settings[key] !== undefined && typeof settings[key] !== "string") {
+			throw new Error(`invalid ${key}`);
+		}
+	}
+	if (settings.enabled !== undefined && typeof settings.enabled !== "boolean") {
+		throw new Error("invalid enabled");
+	}
+	if (settings.preset && !findPreset(presets, settings.preset)) {
+		throw new Error(`unknown preset ${settings.preset}`);
+	}
+	// relative folders would depend on where the app was started
+	if (settings.saveDir && !path.isAbsolute(settings.saveDir)) {
+		throw new Error("the save location must be an absolute path");
+	}
+	const error = settings.template && validateTemplate(settings.template);
+	if (error) throw new Error(error);
+	return settings;
+}
+
+async function fetchFeed(channelId) {
+	return parseFeed(await getText(feedUrl(channelId)));
+}
+
+/**
+ * Channels followed by the app, stored in their own file. Their feeds are
+ * polled while the app runs and uploads published after subscribing are
+ * queued unless the history, the archive or the queue has them already.
+ *
+ * A subscription is { channelId, title, preset, saveDir, template, enabled,
+ * since, lastChecked, error, failed } where preset is the name of a preset
+ * (the best merged formats if empty), saveDir and template override the app
+ * options when set, and failed lists the ids of uploads without a format
+ * matching the preset, which are not tried again. Uploads that failed for
+ * other reasons, like the network or a setting, are tried on the next poll.
+ *
+ * Emits "change" with the list whenever it changes.
+ */
+class Subscriptions extends EventEmitter {
+	/**
+	 * @param {Object} opts
+	 * @param {String} opts.file 	where the subscriptions are saved
+	 * @param {DownloadService} opts.service 	queues the uploads
+	 * @param {Function} opts.getOptions 	returns the current app options
+	 */
+	constructor({ file, service, getOptions }) {
+		super();
+		this.file = file;
+		this.service = service;
+		this.getOptions = getOptions;
+		this.list = [];
+		this.timer = undefined;
+		this.polling = undefined;
+		try {
+			this.list = JSON.parse(fs.readFileSync(file, "utf8"));
+		} catch (err) {}
+	}
+
+	save() {
+		try {
+			fs.mkdirSync(path.dirname(this.file), { recursive: true });
+			fs.writeFileSync(this.file, JSON.stringify(this.list));
+		} catch (err) {
+			console.error(err);
+		}
+		this.emit("change", this.get());
+	}
+
+	get() {
+		return this.list.map((s) => ({ ...s }));
+	}
+
+	/**
+	 * Follow a channel
+	 * @param {String} input 	channel URL, @handle or id
+	 * @param {Object} settings 	{ preset, saveDir, template }
+	 */
+	async add(input, settings = {}) {
+		checkSettings(settings, this.getOptions().presets);
+		const channelId = await resolveChannel(input);
+		if (this.list.some((s) => s.channelId === channelId)) {
+			throw new Error("already subscribed to this channel");
+		}
+		const { title } = await fetchFeed(channelId);
+		this.list.push({
+			channelId,
+			title: title || channelId,
+			preset: settings.preset || "",
+			saveDir: settings.saveDir || "",
+			template: settings.template || "",
+			enabled: true,
+			since: Date.now(),
+			lastChecked: 0,
+			error: "",
+			failed: [],
+		});
+		this.save();
+		this.schedule();
+		return this.get();
+	}
+
+	/**
+	 * Change the settings of a subscription
+	 * @param {String} channelId
+	 * @param {Object} changes 	any of { preset, saveDir, template, enabled }
+	 */
+	update(channelId, changes = {}) {
+		checkSettings(changes, this.getOptions().presets);
+		const sub = this.list.find((s) => s.channelId === channelId);
+		if (!sub) return;
+		for (const key of ["preset", "saveDir", "template", "enabled"]) {
+			if (changes[key] !== undefined) sub[key] = changes[key];
+		}
+		this.save();
+		this.schedule();
+	}
+
+	remove(channelId) {
+		this.list = this.list.filter((s) => s.channelId !== channelId);
+		this.save();
+	}
+
+	/**
+	 * Poll once the subscription checked the longest ago is due, then on the
+	 * interval of the options, while polling is enabled
+	 */
+	schedule() {
+		clearTimeout(this.timer);
+		const settings = { ...DEFAULT_SUBSCRIPTIONS, ...this.getOptions().subscriptions };
+		const checked = this.list.filter((s) => s.enabled).map((s) => s.lastChecked);
+		if (!settings.enabled || !checked.length) return;
+		const minutes = Math.max(5, settings.interval || DEFAULT_SUBSCRIPTIONS.interval);
+		const due = Math.min(...checked) + minutes * 60000;
+		this.timer = setTimeout(async () => {
+			await this.check();
+			this.schedule();
+		}, Math.max(10000, due - Date.now()));
+	}
+
+	/**
+	 * Poll the feeds and queue new uploads
+	 * @param {String} channelId 	only this subscription, even if disabled
+	 * @returns {Promise<Number>} how many videos were queued
+	 */
+	check(channelId) {
+		// a manual check joins a poll that is running rather than racing it
+		if (this.polling) return this.polling.then(() => (channelId ? this.check(channelId) : 0));
+		this.polling = this.poll(channelId).finally(() => (this.polling = undefined));
+		return this.polling;
+	}
+
+	async poll(channelId) {
+		const subs = channelId
+			? this.list.filter((s) => s.channelId === channelId)
+			: this.list.filter((s) => s.enabled);
+		let queued = 0;
+		for (const sub of subs) {
+			sub.error = "";
+			try {
+				const { videos } = await fetchFeed(sub.channelId);
+				const seen = this.seenIds();
+				const failed = new Set(sub.failed || []);
+				const fresh = videos.filter(
+					(v) => v.published > sub.since && !seen.has(v.videoId) && !failed.has(v.videoId)
+				);
+				// oldest first, so the queue follows the channel
+				for (const video of fresh.reverse()) {
+					try {
+						await this.service.queueUrl(video.url, {
+							preset: sub.preset || undefined,
+							saveDir: sub.saveDir || undefined,
+							template: sub.template || undefined,
+						});
+						queued++;
+					} catch (err) {
+						sub.error = `${video.title}: ${err.message || err}`;
+						if (err instanceof FormatError) {
+							sub.failed = [...(sub.failed || []), video.videoId].slice(-MAX_FAILED);
+						}
+					}
+				}
+			} catch (err) {
+				sub.error = `${err.message || err}`;
+			}
+			sub.lastChecked = Date.now();
+			this.save();
+		}
+		return queued;
+	}
+
+	/**
+	 * Ids of the videos that were downloaded, tried or queued already
+	 */
+	seenIds() {
+		const ids = new Set(this.service.archivedIds());
+		for (const entry of this.service.getHistory()) ids.add(entry.videoId);
+		for (const job of this.service.getQueue()) {
+			const m = VIDEO_ID.exec(job.url);
+			if (job.videoId || m) ids.add(job.videoId || m[1]);
+		}
+		return ids;
+	}
+}
+
+module.exports = { DEFAULT_SUBSCRIPTIONS, resolveChannel, parseFeed, Subscriptions };
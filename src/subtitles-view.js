@@ -0,0 +1,91 @@
+import { html } from "../node_modules/lit-html/lit-html.js";
+import { constants } from "./constants.js";
+
+const selected = new Set(); // ids of the tracks picked for the current video
+let translateTo = "";
+
+/**
+ * Forget the tracks picked for the previous video
+ */
+export function resetSubtitles() {
+	selected.clear();
+	translateTo = "";
+}
+
+/**
+ * Tracks picked for jobs queued from the current video, the download service
+ * adds the subtitle settings. Undefined if no track is picked.
+ * @returns {Object} { tracks, translateTo }
+ */
+export function subtitleChoice() {
+	if (!selected.size) return undefined;
+	return { tracks: [...selected], translateTo: translateTo || undefined };
+}
+
+/**
+ * Panel listing the caption tracks of a video
+ * @param {Object} info 	from the download service, with tracks and translations
+ * @param {Object} options 	the app options
+ * @param {Function} setOption 	called with (key, value) to change an option
+ * @param {Function} update 	re-renders the info view
+ */
+export function subtitlesPanel(info, options, setOption, update) {
+	const { tracks, translations } = info;
+	if (!tracks.length) return html`<div class="merge-row">No subtitles</div>`;
+	const settings = { ...constants.defaults.subtitles, ...options.subtitles };
+	const set = (key, value) => setOption("subtitles", { ...settings, [key]: value });
+
+	return html`<details class="subtitles">
+		<summary>Subtitles (${selected.size ? `${selected.size} picked` : tracks.length})</summary>
+		<div class="merge-row">
+			${tracks.map(
+				(t) => html`<label title=${t.id}>
+					<input
+						type="checkbox"
+						.checked=${selected.has(t.id)}
+						@change=${(e) => {
+							if (e.target.checked) selected.add(t.id);
+							else selected.delete(t.id);
+							update();
+						}}
+					/>
+					${t.name || t.languageCode}${t.auto ? " (auto-generated)" : ""}
+				</label>`
+			)}
+		</div>
+		<div class="merge-row">
+			<label>
+				Also translate to
+				<select @change=${(e) => (translateTo = e.target.value)}>
+					<option value="" .selected=${!translateTo}>None</option>
+					${translations.map(
+						(l) => html`<option
+							value=${l.languageCode}
+							.selected=${translateTo === l.languageCode}
+						>
+							${l.name || l.languageCode}
+						</option>`
+					)}
+				</select>
+			</label>
+			<label>
+				Format
+				<select @change=${(e) => set("format", e.target.value)}>
+					${Object.entries(constants.subtitleFormats).map(
+						([key, label]) => html`<option value=${key} .selected=${settings.format === key}>
+							${label}
+						</option>`
+					)}
+				</select>
+			</label>
+			<label title="Only mp4, mkv and webm downloads can hold subtitles">
+				<input
+					type="checkbox"
+					.checked=${settings.embed}
+					@change=${(e) => set("embed", e.target.checked)}
+				/>
+				Embed into the video
+			</label>
+		</div>
+	</details>`;
+}
@@ -0,0 +1,224 @@
+const fs = require("fs");
+const { getText } = require("./http.js");
+
+/**
+ * Default subtitle settings, stored as options.subtitles
+ */
+const DEFAULT_SUBTITLES = { format: "srt", embed: false };
+
+/**
+ * Subtitle file formats, with how ffmpeg stores them in each muxer. Muxers
+ * missing from `codecs` cannot hold subtitles.
+ */
+const SUBTITLE_FORMATS = {
+	srt: { label: "SubRip (.srt)", codecs: { mp4: "mov_text", matroska: "srt", webm: "webvtt" } },
+	vtt: { label: "WebVTT (.vtt)", codecs: { mp4: "mov_text", matroska: "webvtt", webm: "webvtt" } },
+	ass: { label: "ASS (.ass)", codecs: { mp4: "mov_text", matroska: "ass", webm: "webvtt" } },
+};
+
+// ISO 639-2 codes for the languages containers expect, by ISO 639-1 code
+const ISO639_2 = {
+	ar: "ara",
+	de: "ger",
+	en: "eng",
+	es: "spa",
+	fr: "fre",
+	hi: "hin",
+	id: "ind",
+	it: "ita",
+	ja: "jpn",
+	ko: "kor",
+	nl: "dut",
+	pl: "pol",
+	pt: "por",
+	ru: "rus",
+	sv: "swe",
+	th: "tha",
+	tr: "tur",
+	uk: "ukr",
+	vi: "vie",
+	zh: "chi",
+};
+
+/**
+ * Caption tracks of a video, manual ones first
+ * @param {Object} info 	result of ytdl.getInfo()
+ * @returns {Object} { tracks: [{ id, languageCode, name, auto, translatable }],
+ * 					translations: [{ languageCode, name }] }
+ */
+function listTracks(info) {
+	const renderer =
+		info.player_response &&
+		info.player_response.captions &&
+		info.player_response.captions.playerCaptionsTracklistRenderer;
+	if (!renderer) return { tracks: [], translations: [] };
+	const text = (t) => (t ? t.simpleText || (t.runs || []).map((r) => r.text).join("") : "");
+	const tracks = (renderer.captionTracks || []).map((t) => ({
+		id: `${t.languageCode}${t.kind === "asr" ? ".auto" : ""}`,
+		languageCode: t.languageCode,
+		name: text(t.name),
+		auto: t.kind === "asr",
+		translatable: !!t.isTranslatable,
+	}));
+	tracks.sort((a, b) => a.auto - b.auto);
+	const translations = (renderer.translationLanguages || []).map((l) => ({
+		languageCode: l.languageCode,
+		name: text(l.languageName),
+	}));
+	return { tracks, translations };
+}
+
+/**
+ * Container language tag of a track
+ * @param {String} languageCode 	e.g. "en" or "pt-BR"
+ */
+function languageTag(languageCode) {
+	const base = languageCode.split("-")[0];
+	return ISO639_2[base] || base;
+}
+
+function decodeEntities(str) {
+	return str
+		.replace(/&#(\d+);/g, (m, n) => String.fromCodePoint(parseInt(n, 10)))
+		.replace(/&#x([0-9a-f]+);/gi, (m, n) => String.fromCodePoint(parseInt(n, 16)))
+		.replace(/&quot;/g, '"')
+		.replace(/&apos;/g, "'")
+		.replace(/&lt;/g, "<")
+		.replace(/&gt;/g, ">")
+		.replace(/&amp;/g, "&");
+}
+
+/**
+ * Parse YouTube's timedtext XML into cues
+ * @param {String} xml
+ * @returns {Array} [{ start, end, text }] with times in seconds
+ */
+function parseTimedText(xml) {
+	const cues = [];
+	const re = /<text start="([\d.]+)"(?: dur="([\d.]+)")?[^>]*>([\s\S]*?)<\/text>/g;
+	let m;
+	while ((m = re.exec(xml))) {
+		const start = parseFloat(m[1]);
+		// captions are escaped twice, once for XML and once for HTML
+		const text = decodeEntities(decodeEntities(m[3]))
+			.replace(/<[^>]+>/g, "")
+			.trim();
+		if (text) cues.push({ start, end: start + parseFloat(m[2] || "2"), text });
+	}
+	// auto-generated captions overlap, end each cue when the next one starts
+	for (let i = 0; i < cues.length - 1; i++) {
+		cues[i].end = Math.min(cues[i].end, cues[i + 1].start);
+	}
+	return cues;
+}
+
+/**
+ * Download the cues of a track
+ * @param {Object} info 	result of ytdl.getInfo(), fresh since caption URLs expire
+ * @param {String} trackId 	an id from listTracks()
+ * @param {String} translateTo 	language code to auto-translate to, if any
+ */
+async function fetchCues(info, trackId, translateTo) {
+	const renderer = info.player_response.captions.playerCaptionsTracklistRenderer;
+	const track = renderer.captionTracks.find(
+		(t) => `${t.languageCode}${t.kind === "asr" ? ".auto" : ""}` === trackId
+	);
+	if (!track) throw new Error(`subtitles ${trackId} are no longer available`);
+	const url = new URL(track.baseUrl);
+	url.searchParams.delete("fmt");
+	if (translateTo) url.searchParams.set("tlang", translateTo);
+	return parseTimedText(await getText(url.href));
+}
+
+function timestamp(seconds, separator, hourDigits = 2) {
+	const ms = Math.round(seconds * 1000);
+	const h = `${Math.floor(ms / 3600000)}`.padStart(hourDigits, "0");
+	const m = `${Math.floor(ms / 60000) % 60}`.padStart(2, "0");
+	const s = `${Math.floor(ms / 1000) % 60}`.padStart(2, "0");
+	return `${h}:${m}:${s}${separator}${`${ms % 1000}`.padStart(3, "0")}`;
+}
+
+function toVtt(cues) {
+	return (
+		"WEBVTT\n\n" +
+		cues
+			.map((c) => `${timestamp(c.start, ".")} --> ${timestamp(c.end, ".")}\n${c.text}\n`)
+			.join("\n")
+	);
+}
+
+function toSrt(cues) {
+	return cues
+		.map(
+			(c, i) => `${i + 1}\n${timestamp(c.start, ",")} --> ${timestamp(c.end, ",")}\n${c.text}\n`
+		)
+		.join("\n");
+}
+
+function toAss(cues) {
+	// ASS uses h:mm:ss.cc
+	const t = (seconds) => timestamp(seconds, ".", 1).slice(0, -1);
+	return [
+		"[Script Info]",
+		"ScriptType: v4.00+",
+		"PlayResX: 1920",
+		"PlayResY: 1080",
+		"",
+		"[V4+ Styles]",
+		"Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding",
+		"Style: Default,Arial,64,&H00FFFFFF,&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,3,1,2,60,60,50,1",
+		"",
+		"[Events]",
+		"Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text",
+		...cues.map(
+			(c) => `Dialogue: 0,${t(c.start)},${t(c.end)},Default,,0,0,0,,${c.text.replace(/\n/g, "\\N")}`
+		),
+		"",
+	].join("\n");
+}
+
+const WRITERS = { vtt: toVtt, srt: toSrt, ass: toAss };
+
+/**
+ * Download the subtitles a job asked for and write them next to its file
+ * @param {Object} info 	result of ytdl.getInfo()
+ * @param {Object} subtitles 	{ tracks: [trackId], translateTo, format }
+ * @param {String} base 	path of the download without its extension
+ * @returns {Array} [{ file, language, format }] of the files written
+ */
+async function saveSubtitles(info, subtitles, base) {
+	const format = SUBTITLE_FORMATS[subtitles.format] ? subtitles.format : "srt";
+	const { tracks } = listTracks(info);
+	const wanted = [];
+	for (const id of subtitles.tracks) {
+		const track = tracks.find((t) => t.id === id);
+		if (!track) continue;
+		wanted.push({ track, translateTo: undefined });
+		if (subtitles.translateTo && track.translatable && subtitles.translateTo !== track.languageCode) {
+			wanted.push({ track, translateTo: subtitles.translateTo });
+		}
+	}
+
+	const files = [];
+	for (const { track, translateTo } of wanted) {
+		const cues = await fetchCues(info, track.id, translateTo);
+		const language = translateTo || track.languageCode;
+		const suffix = translateTo ? `${track.id}-${translateTo}` : track.id;
+		const file = `${base}.${suffix}.${format}`;
+		fs.writeFileSync(file, WRITERS[format](cues));
+		files.push({ file, language: languageTag(language), format });
+	}
+	return files;
+}
+
+module.exports = {
+	DEFAULT_SUBTITLES,
+	decodeEntities,
+	SUBTITLE_FORMATS,
+	listTracks,
+	parseTimedText,
+	toVtt,
+	toSrt,
+	toAss,
+	saveSubtitles,
+};
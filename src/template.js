@@ -0,0 +1,208 @@
+/**
+ * Filename templates.
+ *
+ * A template is a path relative to the save location where `{field}` is
+ * replaced by a value of the download, e.g.
+ * `{uploader}/{upload_date} - {title} [{id}].{ext}`.
+ *
+ * - `{a|b}` uses field b when a is empty, and a final alternative that is not
+ *   a field is used literally: `{album|Unknown album}`
+ * - `{field:03}` pads numbers with zeros to 3 digits
+ * - `/` starts a subdirectory, `{{` and `}}` are literal braces
+ */
+
+const DEFAULT_TEMPLATE = "{title}-itag{itag}.{ext}";
+const DEFAULT_PLAYLIST_TEMPLATE = "{playlist_index} - {title}-itag{itag}.{ext}";
+const DEFAULT_CHAPTER_TEMPLATE = "{title}/{chapter_index} - {chapter}.{ext}";
+
+/**
+ * Fields templates can use
+ */
+const FIELDS = {
+	title: "Video title",
+	id: "Video id",
+	uploader: "Channel name",
+	channel_id: "Channel id",
+	upload_date: "Upload date as YYYYMMDD",
+	year: "Upload year",
+	month: "Upload month",
+	day: "Upload day",
+	duration: "Length in seconds",
+	itag: "itag of the format, video+audio when merged",
+	format: "Quality label, e.g. 1080p60",
+	height: "Video height",
+	fps: "Frames per second",
+	ext: "File extension",
+	playlist_title: "Title of the playlist",
+	playlist_index: "Position in the playlist, padded to the playlist size",
+	playlist_count: "Number of videos in the playlist",
+	chapter: "Chapter title, when splitting by chapter",
+	chapter_index: "Chapter number, padded to the number of chapters",
+	chapter_count: "Number of chapters",
+};
+
+class TemplateError extends Error {
+	constructor(message, position) {
+		super(position === undefined ? message : `${message} at character ${position + 1}`);
+		this.position = position;
+	}
+}
+
+/**
+ * Split a template into literal strings and field references
+ * @param {String} template
+ * @returns {Array} strings and { alternatives, fallback, pad } objects
+ */
+function parseTemplate(template) {
+	const parts = [];
+	let literal = "";
+	for (let i = 0; i < template.length; i++) {
+		const c = template[i];
+		if (c === "}") {
+			if (template[i + 1] !== "}") throw new TemplateError("unmatched }", i);
+			literal += "}";
+			i++;
+			continue;
+		}
+		if (c !== "{") {
+			literal += c;
+			continue;
+		}
+		if (template[i + 1] === "{") {
+			literal += "{";
+			i++;
+			continue;
+		}
+		const end = template.indexOf("}", i);
+		if (end < 0) throw new TemplateError("unclosed {", i);
+		const body = template.slice(i + 1, end);
+		if (body.includes("{")) throw new TemplateError("unclosed {", i);
+		if (literal) parts.push(literal);
+		literal = "";
+		parts.push(parseField(body, i));
+		i = end;
+	}
+	if (literal) parts.push(literal);
+	return parts;
+}
+
+function parseField(body, position) {
+	let pad = 0;
+	// only a trailing :0N pads, other colons belong to a literal fallback
+	const spec = /:(0\d+)$/.exec(body);
+	if (spec) {
+		pad = parseInt(spec[1], 10);
+		body = body.slice(0, spec.index);
+	}
+	const alternatives = body.split("|").map((a) => a.trim());
+	let fallback;
+	if (alternatives.length > 1 && !(alternatives[alternatives.length - 1] in FIELDS)) {
+		fallback = alternatives.pop();
+	}
+	for (const name of alternatives) {
+		if (name in FIELDS) continue;
+		const [field, spec] = name.split(/:(.*)/);
+		if (spec !== undefined && field in FIELDS) {
+			throw new TemplateError(`invalid padding "${spec}", use e.g. :03`, position);
+		}
+		throw new TemplateError(`unknown field "${name}"`, position);
+	}
+	return { alternatives, fallback, pad };
+}
+
+/**
+ * Check a template, returning an error message or "" if it is usable
+ * @param {String} template
+ */
+function validateTemplate(template) {
+	try {
+		if (!template.trim()) return "template is empty";
+		const parts = parseTemplate(template);
+		if (!parts.some((p) => p.alternatives && p.alternatives.includes("ext"))) {
+			return "template must contain {ext}";
+		}
+		if (/^[\\/]|^[a-zA-Z]:/.test(template)) return "template must be a relative path";
+		const literals = parts.filter((p) => typeof p === "string").join("");
+		if (/(^|[\\/])\.\.([\\/]|$)/.test(literals)) return "template must not contain ..";
+		if (/[\\/]$/.test(template)) return "template must end with a filename";
+		return "";
+	} catch (err) {
+		return err.message;
+	}
+}
+
+/**
+ * Values of the template fields for a download
+ * @param {Object} info 	result of ytdl.getInfo()
+ * @param {Object} extra 	{ formats, ext, playlist, chapter } where formats are
+ * 							the downloaded entries of info.formats, playlist is
+ * 							{ title, index, count } and chapter is
+ * 							{ title, index, count }
+ */
+function templateValues(info, { formats = [], ext = "", playlist, chapter } = {}) {
+	const d = info.videoDetails;
+	const date = (d.publishDate || d.uploadDate || "").slice(0, 10);
+	const [year, month, day] = date.split("-");
+	const video = formats.find((f) => f.hasVideo) || formats[0] || {};
+	return {
+		title: d.title,
+		id: d.videoId,
+		uploader: d.author ? d.author.name : d.ownerChannelName,
+		channel_id: d.channelId || (d.author ? d.author.id : ""),
+		upload_date: date.replace(/-/g, ""),
+		year,
+		month,
+		day,
+		duration: d.lengthSeconds,
+		itag: formats.map((f) => f.itag).join("+"),
+		format: video.qualityLabel,
+		height: video.height,
+		fps: video.fps,
+		ext,
+		playlist_title: playlist ? playlist.title : undefined,
+		playlist_index: playlist
+			? `${playlist.index}`.padStart(`${playlist.count}`.length, "0")
+			: undefined,
+		playlist_count: playlist ? playlist.count : undefined,
+		chapter: chapter ? chapter.title : undefined,
+		chapter_index: chapter ? `${chapter.index}`.padStart(`${chapter.count}`.length, "0") : undefined,
+		chapter_count: chapter ? chapter.count : undefined,
+	};
+}
+
+/**
+ * Fill in a template. Path separators in values are replaced so that only the
+ * template itself can create subdirectories. The result still needs to be
+ * sanitized for the target filesystem.
+ * @param {String} template
+ * @param {Object} values 	from templateValues()
+ * @returns {String} a relative path using / as the separator
+ */
+function renderTemplate(template, values) {
+	const out = parseTemplate(template).map((part) => {
+		if (typeof part === "string") return part;
+		let value = "";
+		for (const name of part.alternatives) {
+			if (values[name] !== undefined && values[name] !== null && `${values[name]}` !== "") {
+				value = `${values[name]}`;
+				break;
+			}
+		}
+		if (!value && part.fallback !== undefined) value = part.fallback;
+		if (part.pad && /^\d+$/.test(value)) value = value.padStart(part.pad, "0");
+		return value.replace(/[\\/]/g, "_");
+	});
+	return out.join("").replace(/\\/g, "/");
+}
+
+module.exports = {
+	DEFAULT_TEMPLATE,
+	DEFAULT_PLAYLIST_TEMPLATE,
+	DEFAULT_CHAPTER_TEMPLATE,
+	FIELDS,
+	TemplateError,
+	parseTemplate,
+	validateTemplate,
+	templateValues,
+	renderTemplate,
+};